@@ -0,0 +1,167 @@
+use crate::json::Query;
+use crate::search::{self, SearchScratch, SearchSpace, TieBreak};
+use crate::{Algorithm, Map, PathfindingResult};
+use serde::Serialize;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+#[derive(Debug, Clone, Default, Eq, PartialEq, Serialize)]
+pub struct BatchStats {
+    pub queries: usize,
+    pub reachable: usize,
+    pub total_cost: usize,
+    pub total_nodes_visited: usize,
+    pub longest_path: usize,
+}
+
+impl BatchStats {
+    pub fn mean_nodes_visited(&self) -> f64 {
+        if self.queries == 0 {
+            0.0
+        } else {
+            self.total_nodes_visited as f64 / self.queries as f64
+        }
+    }
+}
+
+pub struct BatchResult<N = (i32, i32)> {
+    pub results: Vec<Option<PathfindingResult<N>>>,
+    pub stats: BatchStats,
+}
+
+/// Runs every query against the shared `space` on `threads` worker threads, each
+/// with its own scratch buffers. Workers pull the next query index from a shared counter, and
+/// the results are put back into input order, so the output doesn't depend on the thread count
+/// or on scheduling. `threads == 0` uses all available cores.
+pub fn find_paths<S>(
+    space: &S,
+    queries: &[Query<S::Node>],
+    algorithm: Algorithm,
+    threads: usize,
+) -> BatchResult<S::Node>
+where
+    S: SearchSpace + Sync,
+    S::Node: Send + Sync,
+{
+    let threads = match threads {
+        0 => std::thread::available_parallelism().map_or(1, |n| n.get()),
+        n => n,
+    }
+    .min(queries.len())
+    .max(1);
+    let next_query = AtomicUsize::new(0);
+
+    let mut indexed = std::thread::scope(|scope| {
+        let workers = (0..threads)
+            .map(|_| {
+                scope.spawn(|| {
+                    let mut scratch = SearchScratch::<_>::new();
+                    let mut solved = Vec::new();
+                    loop {
+                        let i = next_query.fetch_add(1, Ordering::Relaxed);
+                        let Some(&query) = queries.get(i) else {
+                            break;
+                        };
+                        solved.push((
+                            i,
+                            search::find_path_with(
+                                space,
+                                &mut scratch,
+                                algorithm,
+                                TieBreak::Position,
+                                query.start,
+                                query.goal,
+                            ),
+                        ));
+                    }
+                    solved
+                })
+            })
+            .collect::<Vec<_>>();
+
+        workers
+            .into_iter()
+            .flat_map(|worker| worker.join().expect("Pathfinding worker panicked"))
+            .collect::<Vec<_>>()
+    });
+    indexed.sort_unstable_by_key(|(i, _)| *i);
+
+    let results = indexed
+        .into_iter()
+        .map(|(_, result)| result)
+        .collect::<Vec<_>>();
+    let mut stats = BatchStats {
+        queries: results.len(),
+        ..BatchStats::default()
+    };
+    for result in results.iter().flatten() {
+        stats.reachable += 1;
+        stats.total_cost += result.cost_of_path;
+        stats.total_nodes_visited += result.nodes_visited;
+        stats.longest_path = stats.longest_path.max(result.path.len());
+    }
+
+    BatchResult { results, stats }
+}
+
+impl Map {
+    pub fn find_paths_batch(
+        &self,
+        queries: &[Query],
+        algorithm: Algorithm,
+        threads: usize,
+    ) -> BatchResult {
+        find_paths(self, queries, algorithm, threads)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::json::Query;
+    use crate::search;
+    use crate::{Algorithm, Map};
+    use rand::rngs::StdRng;
+    use rand::{Rng, SeedableRng};
+
+    #[test]
+    fn test_batch_is_deterministic_and_ordered() {
+        let mut rng = StdRng::seed_from_u64(7);
+        let mut map = Map::new(30, 30);
+        map.generate_map(&mut rng, (20, 40));
+        let queries = (0..200)
+            .map(|_| Query {
+                start: (rng.gen_range(0..30), rng.gen_range(0..30)),
+                goal: (rng.gen_range(0..30), rng.gen_range(0..30)),
+            })
+            .collect::<Vec<_>>();
+
+        for algorithm in Algorithm::DEFAULT {
+            let sequential = queries
+                .iter()
+                .map(|q| search::find_path(&map, algorithm, q.start, q.goal))
+                .collect::<Vec<_>>();
+            let single = map.find_paths_batch(&queries, algorithm, 1);
+
+            for threads in [2, 3, 8] {
+                let batch = map.find_paths_batch(&queries, algorithm, threads);
+                assert_eq!(batch.stats, single.stats);
+                for (expected, actual) in sequential.iter().zip(&batch.results) {
+                    assert_eq!(
+                        expected
+                            .as_ref()
+                            .map(|r| (&r.path, r.cost_of_path, r.nodes_visited)),
+                        actual
+                            .as_ref()
+                            .map(|r| (&r.path, r.cost_of_path, r.nodes_visited))
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_empty_batch() {
+        let batch = Map::new(3, 3).find_paths_batch(&[], Algorithm::AStar, 4);
+        assert!(batch.results.is_empty());
+        assert_eq!(batch.stats.queries, 0);
+    }
+}
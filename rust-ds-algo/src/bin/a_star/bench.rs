@@ -0,0 +1,117 @@
+use crate::frontier::{BucketQueue, Frontier, FrontierKind, RadixHeap};
+use crate::json::Query;
+use crate::search::{self, PriorityState, SearchScratch, TieBreak};
+use crate::{Algorithm, Map, MapNode};
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use std::collections::BinaryHeap;
+use std::time::{Duration, Instant};
+
+const SIZES: [usize; 2] = [64, 160];
+/// Factors the tile costs are multiplied with, after raising the free tiles `generate_map` makes
+/// to cost 1. Without free tiles A* scales its heuristic by the cheapest tile, here `scale`,
+/// instead of falling back to Dijkstra.
+const COST_SCALES: [i32; 3] = [1, 25, 1000];
+const QUERIES: usize = 20;
+
+/// Times Dijkstra and A* with every frontier kind on generated maps of growing size and tile
+/// cost. Bucket queues win on small tile costs and fall behind once the path costs, and with
+/// them the number of buckets to sweep, grow large. There plain binary heaps win, with the radix
+/// heap close behind. A*'s heuristic is consistent on these maps, so the radix heap never needs
+/// its fallback, and A* ranks the frontiers the same way Dijkstra does.
+pub fn run(seed: u64) -> String {
+    let mut rng = StdRng::seed_from_u64(seed);
+    let mut table = format!(
+        "{:>6}{:>10}  {:<12}{:>12}{:>12}{:>12}",
+        "Size", "Max cost", "Algorithm", "heap", "bucket", "radix"
+    );
+
+    for size in SIZES {
+        for scale in COST_SCALES {
+            let map = scaled_map(&mut rng, size, scale);
+            let queries = (0..QUERIES)
+                .map(|_| Query {
+                    start: walkable_cell(&mut rng, &map),
+                    goal: walkable_cell(&mut rng, &map),
+                })
+                .collect::<Vec<_>>();
+
+            for algorithm in [Algorithm::Dijkstra, Algorithm::AStar] {
+                let heap = time::<BinaryHeap<PriorityState<_>>>(&map, algorithm, &queries);
+                let bucket = time::<BucketQueue<_>>(&map, algorithm, &queries);
+                let radix = time::<RadixHeap<_>>(&map, algorithm, &queries);
+                // Both searches are optimal, so paths may differ on ties but costs may not.
+                assert!(
+                    heap.1 == bucket.1 && heap.1 == radix.1,
+                    "Frontiers disagree on path costs"
+                );
+
+                let timings = [heap.0, bucket.0, radix.0];
+                let fastest = FrontierKind::ALL[(0..3).min_by_key(|&i| timings[i]).unwrap_or(0)];
+                table.push_str(&format!(
+                    "\n{:>6}{:>10}  {:<12}{:>12}{:>12}{:>12}  {} wins",
+                    format!("{}²", size),
+                    11 * scale,
+                    algorithm.name(),
+                    format_duration(heap.0),
+                    format_duration(bucket.0),
+                    format_duration(radix.0),
+                    fastest.name()
+                ));
+            }
+        }
+    }
+    table
+}
+
+fn scaled_map(rng: &mut StdRng, size: usize, scale: i32) -> Map {
+    let mut map = Map::new(size, size);
+    map.generate_map(rng, (size / 2, size));
+    for node in map.grid.iter_mut().flatten() {
+        if let MapNode::Path(cost) = node {
+            *cost = (*cost).max(1) * scale;
+        }
+    }
+    map
+}
+
+fn walkable_cell(rng: &mut StdRng, map: &Map) -> (i32, i32) {
+    loop {
+        let cell = (
+            rng.gen_range(0..map.height as i32),
+            rng.gen_range(0..map.width as i32),
+        );
+        if map.is_walkable(cell) {
+            return cell;
+        }
+    }
+}
+
+/// Runs all queries on one scratch, returning the time taken and the costs of the found paths.
+fn time<F: Frontier<(i32, i32)>>(
+    map: &Map,
+    algorithm: Algorithm,
+    queries: &[Query],
+) -> (Duration, Vec<Option<usize>>) {
+    let mut scratch = SearchScratch::<_, F>::new();
+    let started = Instant::now();
+    let costs = queries
+        .iter()
+        .map(|query| {
+            search::find_path_with(
+                map,
+                &mut scratch,
+                algorithm,
+                TieBreak::Position,
+                query.start,
+                query.goal,
+            )
+            .map(|res| res.cost_of_path)
+        })
+        .collect();
+    (started.elapsed(), costs)
+}
+
+fn format_duration(duration: Duration) -> String {
+    format!("{:.2}ms", duration.as_secs_f64() * 1000.0)
+}
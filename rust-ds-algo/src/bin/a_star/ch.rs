@@ -0,0 +1,364 @@
+use crate::graph::Graph;
+use crate::search::SearchSpace;
+use crate::PathfindingResult;
+use std::cmp::Reverse;
+use std::collections::{BinaryHeap, HashMap};
+use std::time::{Duration, Instant};
+
+/// Witness searches give up after settling this many nodes and add the shortcut anyway, which
+/// keeps preprocessing fast at the price of a few shortcuts that aren't needed.
+const WITNESS_LIMIT: usize = 64;
+
+/// The graph that is left while contracting, with the edges of every node in both directions.
+struct Remaining {
+    out: Vec<HashMap<usize, usize>>,
+    inc: Vec<HashMap<usize, usize>>,
+    contracted: Vec<bool>,
+}
+
+impl Remaining {
+    fn new(graph: &Graph) -> Self {
+        let mut out = vec![HashMap::new(); graph.len()];
+        let mut inc = vec![HashMap::new(); graph.len()];
+        for (from, edges) in out.iter_mut().enumerate() {
+            for to in graph.neighbors(from) {
+                // Loops never lie on shortest paths.
+                if from != to {
+                    let cost = graph.cost(from, to);
+                    edges.insert(to, cost);
+                    inc[to].insert(from, cost);
+                }
+            }
+        }
+        Remaining {
+            out,
+            inc,
+            contracted: vec![false; graph.len()],
+        }
+    }
+
+    /// Whether a path from `from` to `to` that avoids `skip` and costs at most `limit` exists.
+    fn has_witness(&self, from: usize, to: usize, skip: usize, limit: usize) -> bool {
+        let mut costs = HashMap::new();
+        let mut frontier = BinaryHeap::new();
+        costs.insert(from, 0);
+        frontier.push(Reverse((0, from)));
+        let mut settled = 0;
+
+        while let Some(Reverse((cost, node))) = frontier.pop() {
+            if cost > costs[&node] {
+                continue;
+            }
+            if cost > limit || settled == WITNESS_LIMIT {
+                return false;
+            }
+            if node == to {
+                return true;
+            }
+            settled += 1;
+            for (&next, &step) in &self.out[node] {
+                let cost = cost.saturating_add(step);
+                if next != skip && costs.get(&next).is_none_or(|&known| cost < known) {
+                    costs.insert(next, cost);
+                    frontier.push(Reverse((cost, next)));
+                }
+            }
+        }
+        false
+    }
+
+    /// The shortcuts contracting `node` takes: one for every pair of neighbors only connected
+    /// cheapest through it.
+    fn shortcuts(&self, node: usize) -> Vec<(usize, usize, usize)> {
+        let mut shortcuts = Vec::new();
+        for (&from, &to_node) in &self.inc[node] {
+            for (&to, &from_node) in &self.out[node] {
+                let cost = to_node.saturating_add(from_node);
+                if from != to && !self.has_witness(from, to, node, cost) {
+                    shortcuts.push((from, to, cost));
+                }
+            }
+        }
+        shortcuts
+    }
+
+    /// Contracting nodes that add few shortcuts for the edges they remove first keeps the
+    /// hierarchy small, and counting the neighbors contracted already spreads the order evenly.
+    fn priority(&self, node: usize, contracted_neighbors: &[usize]) -> i64 {
+        let removed = self.inc[node].len() + self.out[node].len();
+        self.shortcuts(node).len() as i64 - removed as i64 + contracted_neighbors[node] as i64
+    }
+}
+
+/// A contraction hierarchy over a `Graph`: nodes are contracted one after the other, cheapest
+/// first, and shortcuts stand in for the shortest paths that ran through them. Every shortest
+/// path then climbs up the order and back down again, so queries only search upward from both
+/// ends, which settles a small fraction of the nodes Dijkstra does. Preprocessing takes a while
+/// and the graph must not change afterwards.
+#[derive(Debug, Clone)]
+pub struct ContractionHierarchy {
+    /// Edges leading to nodes contracted later, by their first node.
+    up: Vec<Vec<(usize, usize)>>,
+    /// Edges coming from nodes contracted later, by their last node.
+    down: Vec<Vec<(usize, usize)>>,
+    /// The node every shortcut skips, by its endpoints.
+    middle: HashMap<(usize, usize), usize>,
+    preprocessing: Duration,
+}
+
+impl ContractionHierarchy {
+    pub fn new(graph: &Graph) -> Self {
+        let started = Instant::now();
+        let mut remaining = Remaining::new(graph);
+        let mut up = vec![Vec::new(); graph.len()];
+        let mut down = vec![Vec::new(); graph.len()];
+        let mut middle = HashMap::new();
+        let mut contracted_neighbors = vec![0; graph.len()];
+
+        let mut queue = (0..graph.len())
+            .map(|node| Reverse((remaining.priority(node, &contracted_neighbors), node)))
+            .collect::<BinaryHeap<_>>();
+        while let Some(Reverse((priority, node))) = queue.pop() {
+            if remaining.contracted[node] {
+                continue;
+            }
+            // Priorities go stale as neighbors are contracted. Recomputing only the one about
+            // to be contracted and putting it back when it lost its place is enough in practice.
+            let current = remaining.priority(node, &contracted_neighbors);
+            if current > priority {
+                queue.push(Reverse((current, node)));
+                continue;
+            }
+
+            for (from, to, cost) in remaining.shortcuts(node) {
+                if remaining.out[from]
+                    .get(&to)
+                    .is_none_or(|&known| cost < known)
+                {
+                    remaining.out[from].insert(to, cost);
+                    remaining.inc[to].insert(from, cost);
+                    middle.insert((from, to), node);
+                }
+            }
+            for (to, cost) in std::mem::take(&mut remaining.out[node]) {
+                remaining.inc[to].remove(&node);
+                contracted_neighbors[to] += 1;
+                up[node].push((to, cost));
+            }
+            for (from, cost) in std::mem::take(&mut remaining.inc[node]) {
+                remaining.out[from].remove(&node);
+                contracted_neighbors[from] += 1;
+                down[node].push((from, cost));
+            }
+            remaining.contracted[node] = true;
+        }
+
+        ContractionHierarchy {
+            up,
+            down,
+            middle,
+            preprocessing: started.elapsed(),
+        }
+    }
+
+    pub fn shortcuts(&self) -> usize {
+        self.middle.len()
+    }
+
+    pub fn preprocessing_time(&self) -> Duration {
+        self.preprocessing
+    }
+
+    /// The cheapest path from `start` to `goal`, with its shortcuts unpacked into the edges of
+    /// the graph. Both searches only follow edges upward and stop once their cheapest open node
+    /// can't improve on the best meeting point found. `nodes_visited` and `nodes_expanded` add
+    /// up both directions.
+    pub fn query(&self, start: usize, goal: usize) -> Option<PathfindingResult<usize>> {
+        let mut searches = [
+            UpwardSearch::new(start, &self.up),
+            UpwardSearch::new(goal, &self.down),
+        ];
+        let mut best: Option<(usize, usize)> = None;
+
+        loop {
+            let bound = best.map_or(usize::MAX, |(cost, _)| cost);
+            let open = searches
+                .iter()
+                .enumerate()
+                .filter_map(|(i, search)| search.peek().filter(|&cost| cost < bound).map(|_| i))
+                .min_by_key(|&i| searches[i].peek());
+            let Some(i) = open else {
+                break;
+            };
+            let Some((node, cost)) = searches[i].settle() else {
+                continue;
+            };
+            if let Some(&(other, _)) = searches[1 - i].costs.get(&node) {
+                let total = cost.saturating_add(other);
+                if best.is_none_or(|(known, _)| total < known) {
+                    best = Some((total, node));
+                }
+            }
+        }
+
+        let (cost_of_path, meeting) = best?;
+        let [forward, backward] = &searches;
+        let mut hops = forward.path_to(meeting);
+        hops.reverse();
+        hops.extend(backward.path_to(meeting).into_iter().skip(1));
+
+        let mut path = vec![start];
+        for hop in hops.windows(2) {
+            self.unpack(hop[0], hop[1], &mut path);
+        }
+        Some(PathfindingResult {
+            nodes_visited: forward.costs.len() + backward.costs.len(),
+            nodes_expanded: forward.expanded + backward.expanded,
+            cost_of_path,
+            path,
+        })
+    }
+
+    /// Appends the nodes after `from` on the edge or shortcut to `to`.
+    fn unpack(&self, from: usize, to: usize, path: &mut Vec<usize>) {
+        let mut stack = vec![(from, to)];
+        while let Some((from, to)) = stack.pop() {
+            match self.middle.get(&(from, to)) {
+                Some(&middle) => {
+                    stack.push((middle, to));
+                    stack.push((from, middle));
+                }
+                None => path.push(to),
+            }
+        }
+    }
+}
+
+/// One half of a query: Dijkstra over the edges of one direction of the hierarchy.
+struct UpwardSearch<'a> {
+    edges: &'a [Vec<(usize, usize)>],
+    costs: HashMap<usize, (usize, usize)>,
+    frontier: BinaryHeap<Reverse<(usize, usize)>>,
+    expanded: usize,
+}
+
+impl<'a> UpwardSearch<'a> {
+    fn new(from: usize, edges: &'a [Vec<(usize, usize)>]) -> Self {
+        UpwardSearch {
+            edges,
+            costs: HashMap::from([(from, (0, from))]),
+            frontier: BinaryHeap::from([Reverse((0, from))]),
+            expanded: 0,
+        }
+    }
+
+    fn peek(&self) -> Option<usize> {
+        self.frontier.peek().map(|Reverse((cost, _))| *cost)
+    }
+
+    /// Expands the cheapest open node and returns it, or nothing if that entry was outdated.
+    fn settle(&mut self) -> Option<(usize, usize)> {
+        let Reverse((cost, node)) = self.frontier.pop()?;
+        if cost > self.costs[&node].0 {
+            return None;
+        }
+        self.expanded += 1;
+        for &(next, step) in &self.edges[node] {
+            let cost = cost.saturating_add(step);
+            if self.costs.get(&next).is_none_or(|&(known, _)| cost < known) {
+                self.costs.insert(next, (cost, node));
+                self.frontier.push(Reverse((cost, next)));
+            }
+        }
+        Some((node, cost))
+    }
+
+    /// The nodes from `to` back to where the search started.
+    fn path_to(&self, to: usize) -> Vec<usize> {
+        let mut path = vec![to];
+        let mut current = to;
+        while self.costs[&current].1 != current {
+            current = self.costs[&current].1;
+            path.push(current);
+        }
+        path
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::ch::ContractionHierarchy;
+    use crate::graph::Graph;
+    use crate::search;
+    use crate::{Map, MapNode};
+    use rand::rngs::StdRng;
+    use rand::{Rng, SeedableRng};
+
+    #[test]
+    fn test_costs_match_dijkstra_on_grids() {
+        for seed in 0..3 {
+            let mut map = Map::new(14, 11);
+            map.generate_map(&mut StdRng::seed_from_u64(seed), (10, 20));
+            let graph = Graph::from(&map);
+            let hierarchy = ContractionHierarchy::new(&graph);
+
+            let mut rng = StdRng::seed_from_u64(seed);
+            for _ in 0..40 {
+                let (start, goal) = (rng.gen_range(0..graph.len()), rng.gen_range(0..graph.len()));
+                let expected = search::dijkstra(&graph, start, goal);
+                let res = hierarchy.query(start, goal);
+                assert_eq!(
+                    res.as_ref().map(|res| res.cost_of_path),
+                    expected.map(|res| res.cost_of_path),
+                    "seed {} from {} to {}",
+                    seed,
+                    graph.name(start),
+                    graph.name(goal)
+                );
+                if let Some(res) = res {
+                    assert_eq!(res.validate(&graph, start, goal), Ok(()));
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_shortcuts_are_unpacked() {
+        // A one way chain with an expensive direct edge, which the contraction has to bridge.
+        let graph: Graph = "a b 1\nb c 1\nc d 1\nd e 1\na e 10\ne a 1\n"
+            .parse()
+            .unwrap();
+        let hierarchy = ContractionHierarchy::new(&graph);
+        let node = |name| graph.node(name).unwrap();
+
+        let res = graph.named(hierarchy.query(node("a"), node("e")).unwrap());
+        assert_eq!(res.path, ["a", "b", "c", "d", "e"]);
+        assert_eq!(res.cost_of_path, 4);
+        assert!(hierarchy.shortcuts() > 0);
+
+        let res = graph.named(hierarchy.query(node("d"), node("b")).unwrap());
+        assert_eq!(res.path, ["d", "e", "a", "b"]);
+        assert_eq!(res.cost_of_path, 3);
+        assert_eq!(hierarchy.query(node("c"), node("c")).unwrap().path.len(), 1);
+
+        for start in 0..graph.len() {
+            for goal in 0..graph.len() {
+                let res = hierarchy.query(start, goal).unwrap();
+                assert_eq!(res.validate(&graph, start, goal), Ok(()));
+            }
+        }
+    }
+
+    #[test]
+    fn test_unreachable_nodes() {
+        let mut map = Map::new(5, 3);
+        for row in 0..3 {
+            map.grid[row][2] = MapNode::VerticalObstacle;
+        }
+        let graph = Graph::from(&map);
+        let hierarchy = ContractionHierarchy::new(&graph);
+        let (start, goal) = (graph.node("0,0").unwrap(), graph.node("2,4").unwrap());
+        assert!(hierarchy.query(start, goal).is_none());
+        assert!(hierarchy.query(start, graph.node("2,1").unwrap()).is_some());
+    }
+}
@@ -0,0 +1,726 @@
+use crate::batch::BatchResult;
+use crate::frontier::FrontierKind;
+use crate::graph::Graph;
+use crate::heatmap::ColorMode;
+use crate::json::Query;
+use crate::search::TieBreak;
+use crate::turns::Heading;
+use crate::{Algorithm, Map, PathfindingResult};
+use itertools::Itertools;
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use serde_json::json;
+use std::str::FromStr;
+
+pub const USAGE: &str = "Usage: a_star [OPTIONS]
+
+Options:
+  --width N               Map width (default 20)
+  --height N              Map height (default 20)
+  --obstacles MIN..MAX    Range of randomly placed obstacles (default 20..50)
+  --seed N                Seed for the map generator, random and reported when omitted
+  --map FILE              Load the map from FILE instead of generating one
+  --save-map FILE         Write the map as JSON to FILE
+  --export-dot FILE       Write the walkable cells of a grid map as a Graphviz graph to FILE
+  --export-edges FILE     Write the walkable cells of a grid map as an edge list to FILE
+  --topology TYPE         grid, hex, voxel or graph (default grid)
+  --floors N              Number of floors of a generated voxel map (default 3)
+  --diagonal              Allow diagonal moves within a voxel floor or with --turns
+  --start [FLOOR,]ROW,COL Agent position (default 0,0)
+  --goal [FLOOR,]ROW,COL  Goal position (default 10,10)
+  --from NAME             Start node of a graph (default the --start cell, named ROW,COL)
+  --to NAME               Goal node of a graph (default the --goal cell, named ROW,COL)
+  --algorithms LIST       Comma separated subset of bfs,dijkstra,gbfs,astar,idastar,fringe
+                          (default bfs,dijkstra,gbfs,astar)
+  --frontier KIND         Priority queue of the best-first searches: heap, bucket or radix
+                          (default heap)
+  --tie-break LIST        Comma separated policies for ordering states of equal cost, each one
+                          run separately: position, higher-g, lower-h, lifo, fifo or
+                          cross-product (default position)
+  --benchmark             Time Dijkstra and A* on every frontier kind and exit
+  --fps N                 Animation speed (default 2)
+  --headless              Skip the animations
+  --format text|json      Output format (default text), json implies --headless
+  --interactive           Start the interactive map editor
+  --fog RADIUS            Explore towards the goal seeing only RADIUS tiles around the agent
+  --heatmap [ALGORITHM]   Show tile costs as colors, with how often ALGORITHM expanded each cell
+  --color MODE            auto, never, 256 or truecolor colors for --heatmap (default auto)
+  --reach BUDGET          Highlight every cell the agent reaches for at most BUDGET
+  --alternatives K        List the K cheapest loopless paths to the goal
+  --max-overlap F         Skip alternatives sharing more than the fraction F of their cells
+                          with a listed one
+  --turns TURN,REVERSE    Find the path of a vehicle paying TURN for every change of heading
+                          and REVERSE for turning around, on top of the tile costs
+  --heading DIR           Direction the vehicle starts facing for --turns: east, north-east,
+                          north, ... (default whichever way it first drives)
+  --contract              Preprocess a contraction hierarchy of a grid or graph and answer the
+                          query with it, reporting the shortcuts added and the time taken
+  --clearance N           Only let the searches pass cells at least N cells away from obstacles
+                          and the map edge, for agents covering 2N-1 by 2N-1 cells
+  --queries FILE          Run every start/goal pair of FILE as a batch
+  --threads N             Worker threads for --queries, 0 uses every core (default 0)
+  -h, --help              Print this message
+
+Map files hold one row per line with whitespace separated tiles: a number is a walkable
+tile with that cost, `|` and `-` are vertical and horizontal obstacles. Voxel maps separate
+their floors with a blank line. Hex maps use the odd-r layout: odd rows are shifted right by
+half a tile and positions are given as ROW,COL of that layout. Files ending in .json hold a
+map as written by --save-map instead, or a list of maps for voxel topologies. Graphs are
+read from edge lists with one FROM TO COST edge or a lone NAME node per line, as written by
+--export-edges.
+
+Query files hold one ROW,COL ROW,COL start/goal pair per line, or a JSON list of
+{\"start\": [ROW, COL], \"goal\": [ROW, COL]} objects when they end in .json.";
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum Format {
+    Text,
+    Json,
+}
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum Topology {
+    Grid,
+    Hex,
+    Voxel,
+    Graph,
+}
+
+#[derive(Debug, Clone)]
+pub struct Options {
+    pub width: usize,
+    pub height: usize,
+    pub obstacles: (usize, usize),
+    pub seed: Option<u64>,
+    pub map_file: Option<String>,
+    pub save_map: Option<String>,
+    pub export_dot: Option<String>,
+    pub export_edges: Option<String>,
+    pub topology: Topology,
+    pub floors: usize,
+    pub diagonal: bool,
+    pub start: (i32, i32),
+    pub start_floor: i32,
+    pub goal: (i32, i32),
+    pub goal_floor: i32,
+    pub from: Option<String>,
+    pub to: Option<String>,
+    pub algorithms: Vec<Algorithm>,
+    pub frontier: FrontierKind,
+    pub tie_breaks: Vec<TieBreak>,
+    pub benchmark: bool,
+    pub fps: usize,
+    pub headless: bool,
+    pub format: Format,
+    pub interactive: bool,
+    pub fog: Option<usize>,
+    pub reach: Option<usize>,
+    pub heatmap: bool,
+    pub overlay: Option<Algorithm>,
+    pub color: Option<ColorMode>,
+    pub alternatives: Option<usize>,
+    pub max_overlap: Option<f64>,
+    pub turns: Option<(usize, usize)>,
+    pub clearance: Option<usize>,
+    pub contract: bool,
+    pub heading: Option<Heading>,
+    pub queries_file: Option<String>,
+    pub threads: usize,
+    pub help: bool,
+}
+
+impl Default for Options {
+    fn default() -> Self {
+        Options {
+            width: 20,
+            height: 20,
+            obstacles: (20, 50),
+            seed: None,
+            map_file: None,
+            save_map: None,
+            export_dot: None,
+            export_edges: None,
+            topology: Topology::Grid,
+            floors: 3,
+            diagonal: false,
+            start: (0, 0),
+            start_floor: 0,
+            goal: (10, 10),
+            goal_floor: 0,
+            from: None,
+            to: None,
+            algorithms: Algorithm::DEFAULT.to_vec(),
+            frontier: FrontierKind::BinaryHeap,
+            tie_breaks: vec![TieBreak::Position],
+            benchmark: false,
+            fps: 2,
+            headless: false,
+            format: Format::Text,
+            interactive: false,
+            fog: None,
+            reach: None,
+            heatmap: false,
+            overlay: None,
+            color: None,
+            alternatives: None,
+            max_overlap: None,
+            turns: None,
+            clearance: None,
+            contract: false,
+            heading: None,
+            queries_file: None,
+            threads: 0,
+            help: false,
+        }
+    }
+}
+
+fn parse_value<T: FromStr>(flag: &str, value: Option<String>) -> Result<T, String> {
+    let value = value.ok_or(format!("Missing value for {}", flag))?;
+    value
+        .parse()
+        .map_err(|_| format!("Invalid value for {}: {}", flag, value))
+}
+
+/// Parses `ROW,COL` or `FLOOR,ROW,COL`, the floor defaults to 0.
+fn parse_pos(flag: &str, value: Option<String>) -> Result<(i32, (i32, i32)), String> {
+    let value: String = parse_value(flag, value)?;
+    let coordinates = value
+        .split(',')
+        .map(|n| n.trim().parse())
+        .collect::<Result<Vec<i32>, _>>();
+    match coordinates.as_deref() {
+        Ok([row, col]) => Ok((0, (*row, *col))),
+        Ok([floor, row, col]) => Ok((*floor, (*row, *col))),
+        _ => Err(format!("Expected [FLOOR,]ROW,COL for {}: {}", flag, value)),
+    }
+}
+
+fn parse_range(flag: &str, value: Option<String>) -> Result<(usize, usize), String> {
+    let value: String = parse_value(flag, value)?;
+    match value.split("..").map(|n| n.trim().parse()).collect_tuple() {
+        Some((Ok(min), Ok(max))) if min < max => Ok((min, max)),
+        _ => Err(format!(
+            "Expected MIN..MAX with MIN < MAX for {}: {}",
+            flag, value
+        )),
+    }
+}
+
+impl Options {
+    pub fn parse<I: IntoIterator<Item = String>>(args: I) -> Result<Options, String> {
+        let mut options = Options::default();
+        let mut args = args.into_iter().peekable();
+
+        while let Some(flag) = args.next() {
+            match flag.as_str() {
+                "--width" => options.width = parse_value(&flag, args.next())?,
+                "--height" => options.height = parse_value(&flag, args.next())?,
+                "--obstacles" => options.obstacles = parse_range(&flag, args.next())?,
+                "--seed" => options.seed = Some(parse_value(&flag, args.next())?),
+                "--map" => options.map_file = Some(parse_value(&flag, args.next())?),
+                "--save-map" => options.save_map = Some(parse_value(&flag, args.next())?),
+                "--export-dot" => options.export_dot = Some(parse_value(&flag, args.next())?),
+                "--export-edges" => options.export_edges = Some(parse_value(&flag, args.next())?),
+                "--topology" => {
+                    options.topology = match parse_value::<String>(&flag, args.next())?.as_str() {
+                        "grid" => Topology::Grid,
+                        "hex" => Topology::Hex,
+                        "voxel" => Topology::Voxel,
+                        "graph" => Topology::Graph,
+                        other => return Err(format!("Unknown topology: {}", other)),
+                    }
+                }
+                "--floors" => options.floors = parse_value(&flag, args.next())?,
+                "--diagonal" => options.diagonal = true,
+                "--start" => (options.start_floor, options.start) = parse_pos(&flag, args.next())?,
+                "--goal" => (options.goal_floor, options.goal) = parse_pos(&flag, args.next())?,
+                "--from" => options.from = Some(parse_value(&flag, args.next())?),
+                "--to" => options.to = Some(parse_value(&flag, args.next())?),
+                "--algorithms" => {
+                    let list: String = parse_value(&flag, args.next())?;
+                    options.algorithms = list
+                        .split(',')
+                        .map(|name| name.trim().parse())
+                        .collect::<Result<_, _>>()?;
+                }
+                "--frontier" => options.frontier = parse_value(&flag, args.next())?,
+                "--tie-break" => {
+                    let list: String = parse_value(&flag, args.next())?;
+                    options.tie_breaks = list
+                        .split(',')
+                        .map(|name| name.trim().parse())
+                        .collect::<Result<_, _>>()?;
+                }
+                "--benchmark" => options.benchmark = true,
+                "--fps" => options.fps = parse_value(&flag, args.next())?,
+                "--headless" => options.headless = true,
+                "--format" => {
+                    options.format = match parse_value::<String>(&flag, args.next())?.as_str() {
+                        "text" => Format::Text,
+                        "json" => Format::Json,
+                        other => return Err(format!("Unknown format: {}", other)),
+                    }
+                }
+                "--interactive" => options.interactive = true,
+                "--fog" => options.fog = Some(parse_value(&flag, args.next())?),
+                "--heatmap" => {
+                    options.heatmap = true;
+                    if args.peek().is_some_and(|next| !next.starts_with('-')) {
+                        options.overlay = Some(parse_value(&flag, args.next())?);
+                    }
+                }
+                "--color" => options.color = Some(parse_value(&flag, args.next())?),
+                "--reach" => options.reach = Some(parse_value(&flag, args.next())?),
+                "--alternatives" => options.alternatives = Some(parse_value(&flag, args.next())?),
+                "--max-overlap" => options.max_overlap = Some(parse_value(&flag, args.next())?),
+                "--turns" => {
+                    let value: String = parse_value(&flag, args.next())?;
+                    options.turns = match value.split(',').map(|n| n.trim().parse()).collect_tuple()
+                    {
+                        Some((Ok(turn), Ok(reverse))) => Some((turn, reverse)),
+                        _ => return Err(format!("Expected TURN,REVERSE for {}: {}", flag, value)),
+                    };
+                }
+                "--contract" => options.contract = true,
+                "--clearance" => options.clearance = Some(parse_value(&flag, args.next())?),
+                "--heading" => options.heading = Some(parse_value(&flag, args.next())?),
+                "--queries" => options.queries_file = Some(parse_value(&flag, args.next())?),
+                "--threads" => options.threads = parse_value(&flag, args.next())?,
+                "-h" | "--help" => options.help = true,
+                other => return Err(format!("Unknown argument: {}", other)),
+            }
+        }
+
+        if options.width == 0 || options.height == 0 || options.floors == 0 {
+            return Err("Map dimensions must be positive".to_string());
+        }
+        if options.topology != Topology::Voxel
+            && (options.start_floor, options.goal_floor) != (0, 0)
+        {
+            return Err("Only voxel maps have floors".to_string());
+        }
+        if options.topology == Topology::Graph && options.map_file.is_none() {
+            return Err("Graphs have to be read from --map".to_string());
+        }
+        if (options.from.is_some() || options.to.is_some()) && options.topology != Topology::Graph {
+            return Err("--from and --to only apply to graphs".to_string());
+        }
+        if (options.export_dot.is_some() || options.export_edges.is_some())
+            && options.topology != Topology::Grid
+        {
+            return Err("Only grid maps can be exported as graphs".to_string());
+        }
+        if options.interactive && options.topology != Topology::Grid {
+            return Err("The interactive editor only supports grid maps".to_string());
+        }
+        if options.queries_file.is_some() && options.topology != Topology::Grid {
+            return Err("Batch queries only support grid maps".to_string());
+        }
+        if options.fog.is_some() && options.topology != Topology::Grid {
+            return Err("Fog of war only supports grid maps".to_string());
+        }
+        if options.heatmap && options.topology != Topology::Grid {
+            return Err("Heatmaps only support grid maps".to_string());
+        }
+        if options.reach.is_some() && options.topology != Topology::Grid {
+            return Err("Reachability only supports grid maps".to_string());
+        }
+        if options.alternatives.is_some() && options.topology != Topology::Grid {
+            return Err("Alternative paths only support grid maps".to_string());
+        }
+        if options.turns.is_some() && options.topology != Topology::Grid {
+            return Err("Turn penalties only support grid maps".to_string());
+        }
+        if options.contract && matches!(options.topology, Topology::Hex | Topology::Voxel) {
+            return Err("Contraction hierarchies only support grids and graphs".to_string());
+        }
+        if options.clearance.is_some() && options.topology != Topology::Grid {
+            return Err("Clearance only supports grid maps".to_string());
+        }
+        if options.heading.is_some() && options.turns.is_none() {
+            return Err("--heading needs --turns".to_string());
+        }
+        if options
+            .max_overlap
+            .is_some_and(|f| !(0.0..=1.0).contains(&f))
+        {
+            return Err("--max-overlap must lie between 0 and 1".to_string());
+        }
+        if options.fps == 0 {
+            return Err("--fps must be positive".to_string());
+        }
+        if options.format == Format::Json {
+            options.headless = true;
+        }
+        // Pick the seed here rather than in the generator so that it can be reported and the
+        // map reproduced.
+        if options.seed.is_none() && options.map_file.is_none() && !options.benchmark {
+            options.seed = Some(rand::random());
+        }
+
+        Ok(options)
+    }
+
+    /// Loads `--map` if given, otherwise generates one map per floor from the size, obstacle and
+    /// seed options. Grid and hex maps have a single floor. The agent is placed at `--start`, and
+    /// the number of generated obstacles is returned alongside.
+    pub fn build_floors(&self) -> Result<(Vec<Map>, Option<usize>), String> {
+        let floor_count = match self.topology {
+            Topology::Voxel => self.floors,
+            _ => 1,
+        };
+        let (mut floors, obstacles) = match &self.map_file {
+            Some(path) => {
+                let text = std::fs::read_to_string(path)
+                    .map_err(|e| format!("Could not read {}: {}", path, e))?;
+                let floors = match self.topology {
+                    Topology::Voxel if is_json(path) => parse_json(path, &text)?,
+                    _ if is_json(path) => vec![parse_json(path, &text)?],
+                    Topology::Voxel => text
+                        .lines()
+                        .group_by(|line| line.trim().is_empty())
+                        .into_iter()
+                        .filter(|(blank, _)| !blank)
+                        .map(|(_, floor)| floor.collect::<Vec<_>>().join("\n").parse())
+                        .collect::<Result<Vec<Map>, _>>()?,
+                    _ => vec![text.parse::<Map>()?],
+                };
+                (floors, None)
+            }
+            None => {
+                let mut rng = match self.seed {
+                    Some(seed) => StdRng::seed_from_u64(seed),
+                    None => StdRng::from_entropy(),
+                };
+                let mut obstacles = 0;
+                let floors = (0..floor_count)
+                    .map(|_| {
+                        let mut map = Map::new(self.width, self.height);
+                        obstacles += map.generate_map(&mut rng, self.obstacles);
+                        map
+                    })
+                    .collect();
+                (floors, Some(obstacles))
+            }
+        };
+
+        if floors
+            .iter()
+            .any(|f| (f.width, f.height) != (floors[0].width, floors[0].height))
+        {
+            return Err("All floors must have the same dimensions".to_string());
+        }
+        for (name, floor, pos) in [
+            ("Start", self.start_floor, self.start),
+            ("Goal", self.goal_floor, self.goal),
+        ] {
+            if floor < 0 || floor as usize >= floors.len() || !floors[0].contains(pos) {
+                return Err(format!(
+                    "{} {:?} lies outside the {}x{} map with {} floor(s)",
+                    name,
+                    pos,
+                    floors[0].width,
+                    floors[0].height,
+                    floors.len()
+                ));
+            }
+        }
+        for floor in &mut floors {
+            floor.agent_pos = self.start;
+        }
+
+        Ok((floors, obstacles))
+    }
+
+    /// Writes `floors` to `--save-map`, a single map unless this is a voxel topology.
+    pub fn save_floors(&self, floors: &[Map]) -> Result<(), String> {
+        let path = match &self.save_map {
+            Some(path) => path,
+            None => return Ok(()),
+        };
+        let json = match self.topology {
+            Topology::Voxel => serde_json::to_string_pretty(floors),
+            _ => serde_json::to_string_pretty(&floors[0]),
+        }
+        .map_err(|e| e.to_string())?;
+        std::fs::write(path, json).map_err(|e| format!("Could not write {}: {}", path, e))
+    }
+
+    /// Writes `map` to `--export-dot` and `--export-edges`.
+    pub fn export_graph(&self, map: &Map) -> Result<(), String> {
+        for (path, text) in [
+            (&self.export_dot, map.to_dot()),
+            (&self.export_edges, map.to_edge_list()),
+        ] {
+            if let Some(path) = path {
+                std::fs::write(path, text)
+                    .map_err(|e| format!("Could not write {}: {}", path, e))?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Reads the edge list of `--map` along with the `--from` and `--to` nodes.
+    pub fn read_graph(&self) -> Result<(Graph, (usize, usize)), String> {
+        let path = self.map_file.as_deref().unwrap_or_default();
+        let graph: Graph = std::fs::read_to_string(path)
+            .map_err(|e| format!("Could not read {}: {}", path, e))?
+            .parse()
+            .map_err(|e| format!("Invalid {}: {}", path, e))?;
+
+        let node = |name: &Option<String>, (row, col): (i32, i32)| {
+            let name = name.clone().unwrap_or(format!("{},{}", row, col));
+            graph
+                .node(&name)
+                .ok_or(format!("{} has no node {}", path, name))
+        };
+        let endpoints = (node(&self.from, self.start)?, node(&self.to, self.goal)?);
+        Ok((graph, endpoints))
+    }
+
+    /// Reads `--queries`, either one `ROW,COL ROW,COL` start/goal pair per line or JSON.
+    pub fn read_queries(&self, map: &Map) -> Result<Vec<Query>, String> {
+        let path = match &self.queries_file {
+            Some(path) => path,
+            None => return Ok(Vec::new()),
+        };
+        let text =
+            std::fs::read_to_string(path).map_err(|e| format!("Could not read {}: {}", path, e))?;
+
+        if is_json(path) {
+            let queries: Vec<Query> = parse_json(path, &text)?;
+            return match queries
+                .iter()
+                .position(|q| !map.contains(q.start) || !map.contains(q.goal))
+            {
+                Some(i) => Err(format!("Query {} lies outside the map", i)),
+                None => Ok(queries),
+            };
+        }
+
+        text.lines()
+            .enumerate()
+            .filter(|(_, line)| !line.trim().is_empty())
+            .map(|(i, line)| {
+                let (start, goal) = line
+                    .split_whitespace()
+                    .map(|pos| parse_pos("query", Some(pos.to_string())))
+                    .collect_tuple()
+                    .ok_or(format!(
+                        "Line {} of {} is not a query: {}",
+                        i + 1,
+                        path,
+                        line
+                    ))?;
+                let (start, goal) = ((start?).1, (goal?).1);
+                if !map.contains(start) || !map.contains(goal) {
+                    return Err(format!("Query on line {} lies outside the map", i + 1));
+                }
+                Ok(Query { start, goal })
+            })
+            .collect()
+    }
+}
+
+fn is_json(path: &str) -> bool {
+    path.ends_with(".json")
+}
+
+fn parse_json<T: DeserializeOwned>(path: &str, text: &str) -> Result<T, String> {
+    serde_json::from_str(text).map_err(|e| format!("Invalid {}: {}", path, e))
+}
+
+/// An algorithm and tie-breaking policy with what they found.
+pub type Run<N> = (Algorithm, TieBreak, Option<PathfindingResult<N>>);
+
+type Results<N> = [Run<N>];
+
+/// The algorithm's title, followed by its tie-breaking policy unless that is the default.
+fn label(algorithm: Algorithm, tie_break: TieBreak) -> String {
+    match (algorithm, tie_break) {
+        (Algorithm::Bfs | Algorithm::IdaStar | Algorithm::Fringe, _) | (_, TieBreak::Position) => {
+            algorithm.title().to_string()
+        }
+        _ => format!("{} [{}]", algorithm.title(), tie_break.name()),
+    }
+}
+
+pub fn format_table<N>(results: &Results<N>) -> String {
+    let width = results
+        .iter()
+        .map(|(algorithm, tie_break, _)| label(*algorithm, *tie_break).chars().count() + 2)
+        .fold(26, usize::max);
+    let mut table = format!(
+        "{:<width$}{:>8}{:>8}{:>10}{:>8}",
+        "Algorithm",
+        "Cost",
+        "Nodes",
+        "Expanded",
+        "Length",
+        width = width
+    );
+    for (algorithm, tie_break, result) in results {
+        let label = label(*algorithm, *tie_break);
+        table.push('\n');
+        table.push_str(&match result {
+            Some(result) => format!(
+                "{:<width$}{:>8}{:>8}{:>10}{:>8}",
+                label,
+                result.cost_of_path,
+                result.nodes_visited,
+                result.nodes_expanded,
+                result.path.len(),
+                width = width
+            ),
+            None => format!("{:<width$}{:>34}", label, "unreachable", width = width),
+        });
+    }
+    table
+}
+
+pub fn format_json<N: Serialize>(
+    options: &Options,
+    (width, height): (usize, usize),
+    (start, goal): (N, N),
+    results: &Results<N>,
+) -> String {
+    json!({
+        "width": width,
+        "height": height,
+        "seed": options.seed,
+        "start": start,
+        "goal": goal,
+        "results": results
+            .iter()
+            .map(|(algorithm, tie_break, result)| json!({
+                "algorithm": algorithm,
+                "tie_break": tie_break,
+                "result": result,
+            }))
+            .collect::<Vec<_>>(),
+    })
+    .to_string()
+}
+
+pub fn format_batch_table(batches: &[(Algorithm, BatchResult)]) -> String {
+    let mut table = format!(
+        "{:<26}{:>9}{:>11}{:>12}{:>12}",
+        "Algorithm", "Queries", "Reachable", "Total cost", "Mean nodes"
+    );
+    for (algorithm, batch) in batches {
+        table.push_str(&format!(
+            "\n{:<26}{:>9}{:>11}{:>12}{:>12.1}",
+            algorithm.title(),
+            batch.stats.queries,
+            batch.stats.reachable,
+            batch.stats.total_cost,
+            batch.stats.mean_nodes_visited()
+        ));
+    }
+    table
+}
+
+pub fn format_batch_json(queries: &[Query], batches: &[(Algorithm, BatchResult)]) -> String {
+    json!({
+        "queries": queries,
+        "batches": batches
+            .iter()
+            .map(|(algorithm, batch)| json!({
+                "algorithm": algorithm,
+                "stats": batch.stats,
+                "results": batch.results,
+            }))
+            .collect::<Vec<_>>(),
+    })
+    .to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::cli::{format_json, Format, Options};
+    use crate::search::TieBreak;
+    use crate::turns::Heading;
+    use crate::Algorithm;
+
+    fn parse(args: &str) -> Result<Options, String> {
+        Options::parse(args.split_whitespace().map(String::from))
+    }
+
+    #[test]
+    fn test_parse_args() {
+        let options = parse(
+            "--width 30 --height 12 --seed 7 --goal 3,4 --algorithms astar,bfs --format json \
+             --tie-break higher-g,fifo --turns 3,8 --heading north-west --clearance 2",
+        )
+        .unwrap();
+
+        assert_eq!((options.width, options.height), (30, 12));
+        assert_eq!(options.seed, Some(7));
+        assert_eq!(options.goal, (3, 4));
+        assert_eq!(options.algorithms, vec![Algorithm::AStar, Algorithm::Bfs]);
+        assert_eq!(options.tie_breaks, vec![TieBreak::HigherG, TieBreak::Fifo]);
+        assert_eq!(options.turns, Some((3, 8)));
+        assert_eq!(options.heading, Some(Heading::NorthWest));
+        assert_eq!(options.clearance, Some(2));
+        assert_eq!(options.format, Format::Json);
+        assert!(options.headless);
+    }
+
+    #[test]
+    fn test_parse_args_errors() {
+        assert!(parse("--width").is_err());
+        assert!(parse("--width ten").is_err());
+        assert!(parse("--goal 3").is_err());
+        assert!(parse("--obstacles 50..20").is_err());
+        assert!(parse("--algorithms astar,dfs").is_err());
+        assert!(parse("--tie-break random").is_err());
+        assert!(parse("--turns 3").is_err());
+        assert!(parse("--turns 1,2 --heading up").is_err());
+        assert!(parse("--heading east").is_err());
+        assert!(parse("--clearance 2 --topology hex").is_err());
+        assert!(parse("--contract --topology voxel").is_err());
+        assert!(parse("--verbose").is_err());
+    }
+
+    #[test]
+    fn test_generated_maps_always_have_a_seed() {
+        let options = parse("--width 15 --height 12").unwrap();
+        let seed = options.seed.expect("a generated map should get a seed");
+        let seeded = parse(&format!("--width 15 --height 12 --seed {}", seed)).unwrap();
+        assert_eq!(
+            options.build_floors().unwrap().0[0].render(),
+            seeded.build_floors().unwrap().0[0].render()
+        );
+
+        assert_eq!(parse("--map map.txt").unwrap().seed, None);
+        let json = format_json(&options, (15, 12), ((0, 0), (1, 1)), &[]);
+        assert!(json.contains(&format!("\"seed\":{}", seed)));
+    }
+
+    #[test]
+    fn test_seeded_maps_are_reproducible() {
+        let options = parse("--seed 42 --width 15 --height 12").unwrap();
+        let (first, _) = options.build_floors().unwrap();
+        let (second, _) = options.build_floors().unwrap();
+
+        assert_eq!(first[0].render(), second[0].render());
+        assert!(parse("--seed 42 --goal 20,0")
+            .unwrap()
+            .build_floors()
+            .is_err());
+
+        let options = parse("--seed 42 --topology voxel --floors 4 --goal 3,1,1").unwrap();
+        assert_eq!(options.build_floors().unwrap().0.len(), 4);
+        assert!(parse("--topology voxel --goal 4,1,1")
+            .unwrap()
+            .build_floors()
+            .is_err());
+        assert!(parse("--goal 1,1,1").is_err());
+        assert!(parse("--topology graph").is_err());
+        assert!(parse("--from a").is_err());
+        assert!(parse("--topology hex --export-dot map.dot").is_err());
+    }
+}
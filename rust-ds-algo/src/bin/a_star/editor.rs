@@ -0,0 +1,232 @@
+use crate::{Algorithm, Map, MapNode, PathfindingResult};
+use std::io::{self, BufRead, Write};
+
+const MAX_TILE_COST: i32 = 99;
+const HELP: &str = "w/a/s/d or arrows: move   o: toggle obstacle   +/-: tile cost   p: set start   \
+g: set goal\n1: BFS   2: Dijkstra   3: GBFS   4: A*   c: clear result   q: quit   (press enter to apply)";
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+enum Key {
+    Up,
+    Down,
+    Left,
+    Right,
+    Char(char),
+}
+
+/// Input is line buffered, so a line may carry several keys. Arrow keys arrive as `ESC [ A..D`.
+fn parse_keys(line: &str) -> Vec<Key> {
+    let mut keys = Vec::new();
+    let mut chars = line.chars().peekable();
+    while let Some(c) = chars.next() {
+        if c == '\x1B' && chars.peek() == Some(&'[') {
+            chars.next();
+            match chars.next() {
+                Some('A') => keys.push(Key::Up),
+                Some('B') => keys.push(Key::Down),
+                Some('C') => keys.push(Key::Right),
+                Some('D') => keys.push(Key::Left),
+                _ => (),
+            }
+        } else if !c.is_whitespace() {
+            keys.push(match c {
+                'w' => Key::Up,
+                's' => Key::Down,
+                'a' => Key::Left,
+                'd' => Key::Right,
+                c => Key::Char(c),
+            });
+        }
+    }
+    keys
+}
+
+pub struct Editor {
+    map: Map,
+    cursor: (i32, i32),
+    goal: (i32, i32),
+    algorithm: Option<Algorithm>,
+    result: Option<PathfindingResult>,
+}
+
+impl Editor {
+    pub fn new(map: Map, goal: (i32, i32)) -> Self {
+        Editor {
+            cursor: map.agent_pos,
+            map,
+            goal,
+            algorithm: None,
+            result: None,
+        }
+    }
+
+    pub fn run<R: BufRead, W: Write>(&mut self, input: R, mut output: W) -> io::Result<()> {
+        self.draw(&mut output)?;
+        for line in input.lines() {
+            for key in parse_keys(&line?) {
+                if !self.handle(key) {
+                    return Ok(());
+                }
+            }
+            self.draw(&mut output)?;
+        }
+        Ok(())
+    }
+
+    /// Applies a single key press, returning `false` once the user asked to quit.
+    fn handle(&mut self, key: Key) -> bool {
+        match key {
+            Key::Up => self.move_cursor((-1, 0)),
+            Key::Down => self.move_cursor((1, 0)),
+            Key::Left => self.move_cursor((0, -1)),
+            Key::Right => self.move_cursor((0, 1)),
+            Key::Char('o') => self.toggle_obstacle(),
+            Key::Char('+') => self.change_cost(1),
+            Key::Char('-') => self.change_cost(-1),
+            Key::Char('p') => self.map.agent_pos = self.cursor,
+            Key::Char('g') => self.goal = self.cursor,
+            // IDA* and fringe search can take minutes on a generated map, which would freeze the
+            // editor, so only the default algorithms get a key.
+            Key::Char(c @ '1'..='4') => {
+                self.algorithm = Some(Algorithm::DEFAULT[c as usize - '1' as usize]);
+            }
+            Key::Char('c') => self.algorithm = None,
+            Key::Char('q') => return false,
+            _ => return true,
+        }
+
+        self.result = self
+            .algorithm
+            .and_then(|algorithm| self.map.find_path(algorithm, self.goal));
+        true
+    }
+
+    fn move_cursor(&mut self, delta: (i32, i32)) {
+        self.cursor = (
+            (self.cursor.0 + delta.0).clamp(0, self.map.height as i32 - 1),
+            (self.cursor.1 + delta.1).clamp(0, self.map.width as i32 - 1),
+        );
+    }
+
+    fn tile_mut(&mut self) -> &mut MapNode {
+        &mut self.map.grid[self.cursor.0 as usize][self.cursor.1 as usize]
+    }
+
+    fn toggle_obstacle(&mut self) {
+        let tile = self.tile_mut();
+        *tile = match tile {
+            MapNode::Path(_) => MapNode::HorizontalObstacle,
+            _ => MapNode::Path(1),
+        };
+    }
+
+    fn change_cost(&mut self, delta: i32) {
+        if let MapNode::Path(cost) = self.tile_mut() {
+            *cost = (*cost + delta).clamp(0, MAX_TILE_COST);
+        }
+    }
+
+    fn frame(&self) -> String {
+        let mut view = self.map.clone();
+        if let Some(result) = &self.result {
+            for p in result.path.iter().skip(1) {
+                view.draw(*p, "☐");
+            }
+        }
+        view.draw(self.goal, "✯");
+        let cursor = format!("\x1B[7m{}\x1B[0m", view.glyph(self.cursor));
+        view.draw(self.cursor, &cursor);
+
+        let tile = match &self.map.grid[self.cursor.0 as usize][self.cursor.1 as usize] {
+            MapNode::Path(cost) => format!("tile cost {}", cost),
+            _ => "obstacle".to_string(),
+        };
+        let status = match (self.algorithm, &self.result) {
+            (None, _) => "Press 1-4 to run a search".to_string(),
+            (Some(algorithm), None) => format!("{} - Unreachable goal", algorithm.title()),
+            (Some(algorithm), Some(result)) => format!(
+                "{} - Overall path cost: {}, Nodes covered: {}, Path length: {}",
+                algorithm.title(),
+                result.cost_of_path,
+                result.nodes_visited,
+                result.path.len()
+            ),
+        };
+
+        format!(
+            "{}\n\nCursor {:?}: {}   Start {:?}   Goal {:?}\n{}\n\n{}\n",
+            view.render(),
+            self.cursor,
+            tile,
+            self.map.agent_pos,
+            self.goal,
+            status,
+            HELP
+        )
+    }
+
+    fn draw<W: Write>(&self, output: &mut W) -> io::Result<()> {
+        write!(output, "\x1B[2J\x1B[H{}", self.frame())?;
+        output.flush()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::editor::{parse_keys, Editor, Key};
+    use crate::{Algorithm, Map, MapNode};
+
+    #[test]
+    fn test_parse_keys() {
+        assert_eq!(
+            parse_keys("wa \x1B[C\x1B[Bo+"),
+            vec![
+                Key::Up,
+                Key::Left,
+                Key::Right,
+                Key::Down,
+                Key::Char('o'),
+                Key::Char('+')
+            ]
+        );
+    }
+
+    #[test]
+    fn test_scripted_session() {
+        let mut map = Map::new(3, 3);
+        for row in &mut map.grid {
+            for node in row.iter_mut() {
+                *node = MapNode::Path(1);
+            }
+        }
+        let mut editor = Editor::new(map, (0, 2));
+        let mut output = Vec::new();
+
+        editor
+            .run("4\nd o\ns+++\nq\nd\n".as_bytes(), &mut output)
+            .unwrap();
+
+        assert_eq!(editor.cursor, (1, 1));
+        assert!(matches!(editor.map.grid[0][1], MapNode::HorizontalObstacle));
+        assert!(matches!(editor.map.grid[1][1], MapNode::Path(4)));
+        assert_eq!(editor.algorithm, Some(Algorithm::AStar));
+        let result = editor.result.as_ref().unwrap();
+        assert!(!result.path.contains(&(0, 1)));
+        assert_eq!(result.path.last(), Some(&(0, 2)));
+        assert!(String::from_utf8(output)
+            .unwrap()
+            .contains("A* - Overall path cost"));
+    }
+
+    #[test]
+    fn test_only_the_default_algorithms_have_keys() {
+        let mut editor = Editor::new(Map::new(3, 3), (2, 2));
+        for (key, algorithm) in ('1'..='4').zip(Algorithm::DEFAULT) {
+            editor.handle(Key::Char(key));
+            assert_eq!(editor.algorithm, Some(algorithm));
+        }
+        editor.handle(Key::Char('5'));
+        editor.handle(Key::Char('6'));
+        assert_eq!(editor.algorithm, Some(Algorithm::AStar));
+    }
+}
@@ -0,0 +1,204 @@
+use crate::search;
+use crate::{Map, MapNode};
+use serde::Serialize;
+
+/// What an agent that only sees `radius` tiles around itself assumes about the rest of the map:
+/// every unseen tile is walkable at cost 1.
+const UNKNOWN: MapNode = MapNode::Path(1);
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Exploration {
+    pub reached: bool,
+    /// Number of steps taken.
+    pub distance: usize,
+    /// Sum of the tile costs of those steps.
+    pub cost: usize,
+    pub replans: usize,
+    pub trail: Vec<(i32, i32)>,
+    #[serde(skip)]
+    known: Map,
+    #[serde(skip)]
+    seen: Vec<Vec<bool>>,
+}
+
+impl Exploration {
+    /// The map as the agent knows it at the end, with its trail drawn in and the tiles it never
+    /// saw shaded.
+    pub fn known_map(&self) -> Map {
+        let mut map = self.known.clone();
+        for &node in &self.trail[..self.trail.len() - 1] {
+            map.draw(node, "☐");
+        }
+        for (row, seen) in self.seen.iter().enumerate() {
+            for (col, _) in seen.iter().enumerate().filter(|(_, seen)| !**seen) {
+                map.draw((row as i32, col as i32), "░");
+            }
+        }
+        map
+    }
+}
+
+impl Map {
+    /// Walks the agent towards `goal` while only knowing the tiles within `radius` steps (in
+    /// Chebyshev distance) of the places it has been. The agent plans with A* on what it knows
+    /// and replans whenever a newly seen obstacle lies on the rest of its path, or when it bumps
+    /// into one it could not see. It gives up once the known map has no path left.
+    pub fn explore(&self, goal: (i32, i32), radius: usize) -> Exploration {
+        let mut known = Map::new(self.width, self.height);
+        for node in known.grid.iter_mut().flatten() {
+            *node = UNKNOWN;
+        }
+        known.agent_pos = self.agent_pos;
+        let mut exploration = Exploration {
+            reached: false,
+            distance: 0,
+            cost: 0,
+            replans: 0,
+            trail: vec![self.agent_pos],
+            known,
+            seen: vec![vec![false; self.width]; self.height],
+        };
+        self.sense(&mut exploration, radius);
+
+        let mut planned = false;
+        'plan: while exploration.known.agent_pos != goal {
+            let pos = exploration.known.agent_pos;
+            let plan = match search::a_star(&exploration.known, pos, goal) {
+                Some(plan) => plan,
+                None => return exploration,
+            };
+            if planned {
+                exploration.replans += 1;
+            }
+            planned = true;
+
+            for (i, &step) in plan.path.iter().enumerate().skip(1) {
+                // With a small radius the next tile may still be unseen. Bumping into it reveals
+                // the obstacle without moving.
+                if !self.is_walkable(step) {
+                    self.reveal(&mut exploration, step);
+                    continue 'plan;
+                }
+                exploration.known.agent_pos = step;
+                exploration.distance += 1;
+                exploration.cost = exploration.cost.saturating_add(self.cost_adjacent(step));
+                exploration.trail.push(step);
+                self.sense(&mut exploration, radius);
+
+                if plan.path[i + 1..]
+                    .iter()
+                    .any(|&node| !exploration.known.is_walkable(node))
+                {
+                    continue 'plan;
+                }
+            }
+        }
+
+        exploration.reached = true;
+        exploration
+    }
+
+    /// Copies the tiles around the agent from the real map into the known one.
+    fn sense(&self, exploration: &mut Exploration, radius: usize) {
+        let radius = radius as i32;
+        let (row, col) = exploration.known.agent_pos;
+        for r in row - radius..=row + radius {
+            for c in col - radius..=col + radius {
+                self.reveal(exploration, (r, c));
+            }
+        }
+    }
+
+    /// Copies a single tile from the real map into the known one, if it lies on the map.
+    fn reveal(&self, exploration: &mut Exploration, (row, col): (i32, i32)) {
+        if let Some(tile) = self.tile((row, col)) {
+            exploration.known.grid[row as usize][col as usize] = tile.clone();
+            exploration.seen[row as usize][col as usize] = true;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::fog::Exploration;
+    use crate::{Map, MapNode, PathfindingResult};
+
+    fn open_map(width: usize, height: usize) -> Map {
+        let mut map = Map::new(width, height);
+        for node in map.grid.iter_mut().flatten() {
+            *node = MapNode::Path(1);
+        }
+        map
+    }
+
+    /// Checks the trail as a path on the real map, which also checks the cost of the walk.
+    fn assert_valid_trail(map: &Map, exploration: &Exploration) {
+        assert_eq!(exploration.distance + 1, exploration.trail.len());
+        let walked = PathfindingResult {
+            nodes_visited: 0,
+            nodes_expanded: 0,
+            cost_of_path: exploration.cost,
+            path: exploration.trail.clone(),
+        };
+        let end = *exploration.trail.last().unwrap();
+        assert_eq!(walked.validate(map, map.agent_pos, end), Ok(()));
+    }
+
+    #[test]
+    fn test_open_map_needs_no_replans() {
+        let map = open_map(8, 8);
+        let exploration = map.explore((7, 7), 1);
+        assert!(exploration.reached);
+        assert_valid_trail(&map, &exploration);
+        assert_eq!(exploration.replans, 0);
+        assert_eq!(exploration.distance, 14);
+        assert_eq!(exploration.trail.last(), Some(&(7, 7)));
+    }
+
+    #[test]
+    fn test_hidden_wall_forces_a_replan() {
+        // A wall across row 4 with a gap at the far right, invisible from the start.
+        let mut map = open_map(10, 8);
+        for col in 0..9 {
+            map.grid[4][col] = MapNode::HorizontalObstacle;
+        }
+
+        let exploration = map.explore((7, 0), 1);
+        assert!(exploration.reached);
+        assert!(exploration.replans >= 1);
+        assert!(exploration.distance > 7);
+        assert_valid_trail(&map, &exploration);
+        assert!(exploration.trail.contains(&(4, 9)));
+
+        let known = exploration.known_map().render();
+        assert!(known.contains('░'));
+        assert!(known.contains('━'));
+    }
+
+    #[test]
+    fn test_blind_agent_bumps_into_walls() {
+        let mut map = open_map(10, 8);
+        for col in 0..9 {
+            map.grid[4][col] = MapNode::HorizontalObstacle;
+        }
+
+        let exploration = map.explore((7, 0), 0);
+        assert!(exploration.reached);
+        assert!(exploration.replans >= 1);
+        assert_eq!(exploration.cost, exploration.distance);
+        assert_valid_trail(&map, &exploration);
+        assert!(exploration.trail.contains(&(4, 9)));
+    }
+
+    #[test]
+    fn test_enclosed_goal_is_given_up() {
+        let mut map = open_map(6, 6);
+        for node in [(4, 5), (5, 4)] {
+            map.grid[node.0 as usize][node.1 as usize] = MapNode::VerticalObstacle;
+        }
+
+        let exploration = map.explore((5, 5), 2);
+        assert!(!exploration.reached);
+        assert_valid_trail(&map, &exploration);
+    }
+}
@@ -0,0 +1,273 @@
+use crate::search::PriorityState;
+use std::collections::BinaryHeap;
+use std::str::FromStr;
+
+/// The open set of a best-first search: hands out the state with the lowest cost first.
+pub trait Frontier<N>: Default {
+    fn push(&mut self, state: PriorityState<N>);
+    fn pop(&mut self) -> Option<PriorityState<N>>;
+    fn clear(&mut self);
+}
+
+impl<N: Ord> Frontier<N> for BinaryHeap<PriorityState<N>> {
+    fn push(&mut self, state: PriorityState<N>) {
+        BinaryHeap::push(self, state);
+    }
+
+    fn pop(&mut self) -> Option<PriorityState<N>> {
+        BinaryHeap::pop(self)
+    }
+
+    fn clear(&mut self) {
+        BinaryHeap::clear(self);
+    }
+}
+
+/// Dial's bucket queue: one bucket per integer cost and a cursor sweeping upwards. Finding the
+/// cheapest bucket is O(1) amortized, but it needs a bucket for every cost up to the largest one
+/// pushed, so it only pays off while path costs stay small. Every bucket is a small binary heap
+/// that orders its states by their tie-breaking key.
+pub struct BucketQueue<N> {
+    buckets: Vec<BinaryHeap<PriorityState<N>>>,
+    cursor: usize,
+    len: usize,
+}
+
+impl<N> Default for BucketQueue<N> {
+    fn default() -> Self {
+        BucketQueue {
+            buckets: Vec::new(),
+            cursor: 0,
+            len: 0,
+        }
+    }
+}
+
+impl<N: Ord> Frontier<N> for BucketQueue<N> {
+    fn push(&mut self, state: PriorityState<N>) {
+        if state.cost >= self.buckets.len() {
+            self.buckets.resize_with(state.cost + 1, BinaryHeap::new);
+        }
+        // Inconsistent heuristics can push below the cursor, so it has to be able to go back.
+        self.cursor = self.cursor.min(state.cost);
+        let cost = state.cost;
+        self.buckets[cost].push(state);
+        self.len += 1;
+    }
+
+    fn pop(&mut self) -> Option<PriorityState<N>> {
+        if self.len == 0 {
+            return None;
+        }
+        while self.buckets[self.cursor].is_empty() {
+            self.cursor += 1;
+        }
+        self.len -= 1;
+        self.buckets[self.cursor].pop()
+    }
+
+    fn clear(&mut self) {
+        // Everything below the cursor is empty already.
+        let cursor = self.cursor.min(self.buckets.len());
+        for bucket in &mut self.buckets[cursor..] {
+            bucket.clear();
+        }
+        self.cursor = 0;
+        self.len = 0;
+    }
+}
+
+/// A radix heap: bucket `i` holds the states whose cost first differs from the last popped cost
+/// in bit `i - 1`. Every state moves to a lower bucket at most once per bit, so a pop costs
+/// O(log C) amortized for the largest cost C, no matter how far apart the costs are.
+///
+/// Radix heaps need costs that never drop below the last popped one. Those that do, which only
+/// happens with an inconsistent heuristic, are kept in a small binary heap that is drained first.
+/// The buckets are binary heaps as well, so that the states of bucket 0, which all cost the same,
+/// come out in tie-breaking order.
+pub struct RadixHeap<N> {
+    buckets: Vec<BinaryHeap<PriorityState<N>>>,
+    last: usize,
+    len: usize,
+    below_last: BinaryHeap<PriorityState<N>>,
+}
+
+impl<N> RadixHeap<N> {
+    fn bucket(&self, cost: usize) -> usize {
+        (usize::BITS - (cost ^ self.last).leading_zeros()) as usize
+    }
+}
+
+impl<N: Ord> Default for RadixHeap<N> {
+    fn default() -> Self {
+        RadixHeap {
+            buckets: (0..=usize::BITS).map(|_| BinaryHeap::new()).collect(),
+            last: 0,
+            len: 0,
+            below_last: BinaryHeap::new(),
+        }
+    }
+}
+
+impl<N: Ord> Frontier<N> for RadixHeap<N> {
+    fn push(&mut self, state: PriorityState<N>) {
+        if state.cost < self.last {
+            self.below_last.push(state);
+            return;
+        }
+        let bucket = self.bucket(state.cost);
+        self.buckets[bucket].push(state);
+        self.len += 1;
+    }
+
+    fn pop(&mut self) -> Option<PriorityState<N>> {
+        if let Some(state) = self.below_last.pop() {
+            return Some(state);
+        }
+        if self.len == 0 {
+            return None;
+        }
+        if self.buckets[0].is_empty() {
+            let (i, bucket) = self
+                .buckets
+                .iter_mut()
+                .enumerate()
+                .find(|(_, bucket)| !bucket.is_empty())?;
+            let states = std::mem::take(bucket);
+            self.last = states.iter().map(|s| s.cost).min()?;
+            for state in states {
+                let bucket = self.bucket(state.cost);
+                debug_assert!(bucket < i);
+                self.buckets[bucket].push(state);
+            }
+        }
+        self.len -= 1;
+        self.buckets[0].pop()
+    }
+
+    fn clear(&mut self) {
+        for bucket in &mut self.buckets {
+            bucket.clear();
+        }
+        self.below_last.clear();
+        self.last = 0;
+        self.len = 0;
+    }
+}
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum FrontierKind {
+    BinaryHeap,
+    Bucket,
+    Radix,
+}
+
+impl FrontierKind {
+    pub const ALL: [FrontierKind; 3] = [
+        FrontierKind::BinaryHeap,
+        FrontierKind::Bucket,
+        FrontierKind::Radix,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            FrontierKind::BinaryHeap => "heap",
+            FrontierKind::Bucket => "bucket",
+            FrontierKind::Radix => "radix",
+        }
+    }
+}
+
+impl FromStr for FrontierKind {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        FrontierKind::ALL
+            .iter()
+            .find(|kind| kind.name() == s.to_lowercase())
+            .copied()
+            .ok_or(format!("Unknown frontier: {}", s))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::frontier::{BucketQueue, Frontier, RadixHeap};
+    use crate::search::PriorityState;
+    use rand::rngs::StdRng;
+    use rand::{Rng, SeedableRng};
+    use std::collections::BinaryHeap;
+
+    /// Interleaves pushes and pops the way a search does, mostly at or above the last popped
+    /// cost but now and then below it. Costs and tie-breaking keys repeat, positions don't.
+    fn drain_order<F: Frontier<usize>>(seed: u64) -> Vec<(usize, usize)> {
+        let mut rng = StdRng::seed_from_u64(seed);
+        let mut frontier = F::default();
+        let mut popped = Vec::new();
+        let mut last = 0;
+        for pos in 0..2000 {
+            let cost = if rng.gen_bool(0.05) {
+                last / 2
+            } else {
+                last + rng.gen_range(0..1000)
+            };
+            let tie = rng.gen_range(0..3);
+            frontier.push(PriorityState { cost, tie, pos });
+            if rng.gen_bool(0.5) {
+                let state = frontier.pop().unwrap();
+                last = state.cost;
+                popped.push((state.cost, state.pos));
+            }
+        }
+        while let Some(state) = frontier.pop() {
+            popped.push((state.cost, state.pos));
+        }
+        popped
+    }
+
+    #[test]
+    fn test_frontiers_pop_in_the_same_order() {
+        for seed in 0..5 {
+            let expected = drain_order::<BinaryHeap<PriorityState<usize>>>(seed);
+            assert_eq!(expected.len(), 2000);
+            assert_eq!(drain_order::<BucketQueue<usize>>(seed), expected);
+            assert_eq!(drain_order::<RadixHeap<usize>>(seed), expected);
+        }
+    }
+
+    #[test]
+    fn test_clear() {
+        let mut bucket = BucketQueue::default();
+        let mut radix = RadixHeap::default();
+        for cost in [5, 3, 9] {
+            bucket.push(PriorityState {
+                cost,
+                tie: 0,
+                pos: cost,
+            });
+            radix.push(PriorityState {
+                cost,
+                tie: 0,
+                pos: cost,
+            });
+        }
+        assert_eq!(bucket.pop().map(|s| s.pos), Some(3));
+        assert_eq!(radix.pop().map(|s| s.pos), Some(3));
+        bucket.clear();
+        radix.clear();
+        assert!(bucket.pop().is_none() && radix.pop().is_none());
+
+        bucket.push(PriorityState {
+            cost: 1,
+            tie: 0,
+            pos: 1,
+        });
+        radix.push(PriorityState {
+            cost: 1,
+            tie: 0,
+            pos: 1,
+        });
+        assert_eq!(bucket.pop().map(|s| s.cost), Some(1));
+        assert_eq!(radix.pop().map(|s| s.cost), Some(1));
+    }
+}
@@ -0,0 +1,289 @@
+use crate::search::SearchSpace;
+use crate::{Map, PathfindingResult};
+use std::collections::HashMap;
+use std::str::FromStr;
+
+/// Name of a cell in exported graphs.
+fn cell_name((row, col): (i32, i32)) -> String {
+    format!("{},{}", row, col)
+}
+
+impl Map {
+    fn walkable_cells(&self) -> impl Iterator<Item = (i32, i32)> + '_ {
+        (0..self.height as i32)
+            .flat_map(move |row| (0..self.width as i32).map(move |col| (row, col)))
+            .filter(move |&node| self.is_walkable(node))
+    }
+
+    /// One `FROM TO COST` line per step between walkable cells, see `Graph`. Steps are directed
+    /// since they cost what the tile stepped onto costs, and cells without any are listed alone.
+    pub fn to_edge_list(&self) -> String {
+        let mut text = String::from("# FROM TO COST, cells are named ROW,COL\n");
+        for node in self.walkable_cells() {
+            let mut neighbors = self.neighbors(node).peekable();
+            if neighbors.peek().is_none() {
+                text.push_str(&format!("{}\n", cell_name(node)));
+            }
+            for neighbor in neighbors {
+                text.push_str(&format!(
+                    "{} {} {}\n",
+                    cell_name(node),
+                    cell_name(neighbor),
+                    self.cost_adjacent(neighbor)
+                ));
+            }
+        }
+        text
+    }
+
+    /// A Graphviz digraph of the walkable cells, pinned to their place on the grid so that
+    /// `neato -n` draws it like the map. The agent's cell is drawn as a box.
+    pub fn to_dot(&self) -> String {
+        let mut text = String::from("digraph map {\n    node [shape=circle];\n");
+        for node in self.walkable_cells() {
+            let shape = if node == self.agent_pos {
+                ", shape=box"
+            } else {
+                ""
+            };
+            text.push_str(&format!(
+                "    \"{}\" [pos=\"{},{}\"{}];\n",
+                cell_name(node),
+                node.1 * 72,
+                -node.0 * 72,
+                shape
+            ));
+        }
+        for node in self.walkable_cells() {
+            // Only as a label: Graphviz reads `weight` as a layout hint, not as a cost.
+            for neighbor in self.neighbors(node) {
+                text.push_str(&format!(
+                    "    \"{}\" -> \"{}\" [label=\"{}\"];\n",
+                    cell_name(node),
+                    cell_name(neighbor),
+                    self.cost_adjacent(neighbor)
+                ));
+            }
+        }
+        text.push_str("}\n");
+        text
+    }
+}
+
+/// A directed graph with named nodes and weighted edges, read from an edge list: one
+/// `FROM TO COST` edge or a lone `NAME` node per line, with `#` starting a comment. Nodes are
+/// numbered in order of appearance. Of parallel edges only the cheapest is kept.
+#[derive(Debug, Clone, Default)]
+pub struct Graph {
+    names: Vec<String>,
+    ids: HashMap<String, usize>,
+    edges: Vec<Vec<(usize, usize)>>,
+}
+
+impl Graph {
+    pub fn len(&self) -> usize {
+        self.names.len()
+    }
+
+    pub fn edge_count(&self) -> usize {
+        self.edges.iter().map(Vec::len).sum()
+    }
+
+    pub fn node(&self, name: &str) -> Option<usize> {
+        self.ids.get(name).copied()
+    }
+
+    pub fn name(&self, node: usize) -> &str {
+        &self.names[node]
+    }
+
+    /// `result` with its nodes replaced by their names.
+    pub fn named(&self, result: PathfindingResult<usize>) -> PathfindingResult<String> {
+        PathfindingResult {
+            nodes_visited: result.nodes_visited,
+            nodes_expanded: result.nodes_expanded,
+            cost_of_path: result.cost_of_path,
+            path: result
+                .path
+                .into_iter()
+                .map(|node| self.name(node).to_string())
+                .collect(),
+        }
+    }
+
+    fn add_node(&mut self, name: &str) -> usize {
+        if let Some(id) = self.node(name) {
+            return id;
+        }
+        self.names.push(name.to_string());
+        self.ids.insert(name.to_string(), self.names.len() - 1);
+        self.edges.push(Vec::new());
+        self.names.len() - 1
+    }
+
+    fn add_edge(&mut self, from: usize, to: usize, cost: usize) {
+        match self.edges[from].iter_mut().find(|(node, _)| *node == to) {
+            Some((_, known)) => *known = cost.min(*known),
+            None => self.edges[from].push((to, cost)),
+        }
+    }
+}
+
+/// The walkable cells of a map and the steps between them, named like `Map::to_edge_list` does.
+impl From<&Map> for Graph {
+    fn from(map: &Map) -> Self {
+        let mut graph = Graph::default();
+        for node in map.walkable_cells() {
+            let from = graph.add_node(&cell_name(node));
+            for neighbor in map.neighbors(node) {
+                let to = graph.add_node(&cell_name(neighbor));
+                graph.add_edge(from, to, map.cost_adjacent(neighbor));
+            }
+        }
+        graph
+    }
+}
+
+impl FromStr for Graph {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut graph = Graph::default();
+        for (i, line) in s.lines().enumerate() {
+            let line = line.split('#').next().unwrap_or_default();
+            match line.split_whitespace().collect::<Vec<_>>()[..] {
+                [] => {}
+                [name] => {
+                    graph.add_node(name);
+                }
+                [from, to, cost] => {
+                    let cost = cost
+                        .parse()
+                        .map_err(|_| format!("Invalid cost on line {}: {}", i + 1, cost))?;
+                    let (from, to) = (graph.add_node(from), graph.add_node(to));
+                    graph.add_edge(from, to, cost);
+                }
+                _ => {
+                    return Err(format!(
+                        "Expected FROM TO COST on line {}: {}",
+                        i + 1,
+                        line.trim()
+                    ))
+                }
+            }
+        }
+
+        // The searches add up at most one edge per node, so that sum must fit into a usize.
+        let largest = graph.edges.iter().flatten().map(|&(_, cost)| cost).max();
+        if let Some(largest) = largest.filter(|cost| cost.checked_mul(graph.len()).is_none()) {
+            return Err(format!(
+                "Edge cost {} is too large, a path over {} nodes could overflow",
+                largest,
+                graph.len()
+            ));
+        }
+        Ok(graph)
+    }
+}
+
+pub struct GraphNeighbors<'a> {
+    edges: std::slice::Iter<'a, (usize, usize)>,
+}
+
+impl<'a> Iterator for GraphNeighbors<'a> {
+    type Item = usize;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.edges.next().map(|&(to, _)| to)
+    }
+}
+
+impl SearchSpace for Graph {
+    type Node = usize;
+    type Neighbors<'a> = GraphNeighbors<'a>;
+
+    fn neighbors(&self, node: usize) -> GraphNeighbors<'_> {
+        GraphNeighbors {
+            edges: self.edges[node].iter(),
+        }
+    }
+
+    fn cost(&self, from: usize, to: usize) -> usize {
+        self.edges[from]
+            .iter()
+            .find(|(node, _)| *node == to)
+            .map_or(usize::MAX, |&(_, cost)| cost)
+    }
+
+    /// Nodes have no positions to estimate distances from, so the informed searches fall back
+    /// to uninformed ones.
+    fn heuristic(&self, _a: usize, _b: usize) -> usize {
+        0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::graph::Graph;
+    use crate::search;
+    use crate::{Algorithm, Map, MapNode};
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+
+    #[test]
+    fn test_edge_list_round_trip_matches_the_grid() {
+        let mut map = Map::new(12, 12);
+        map.generate_map(&mut StdRng::seed_from_u64(4), (10, 20));
+        map.grid[0][0] = MapNode::Path(1);
+        let graph: Graph = map.to_edge_list().parse().unwrap();
+
+        let walkable = map.grid.iter().flatten();
+        let walkable = walkable.filter(|node| matches!(node, MapNode::Path(_)));
+        assert_eq!(graph.len(), walkable.count());
+        let start = graph.node("0,0").unwrap();
+        for goal in [(11, 11), (5, 7), (0, 9)] {
+            let expected = search::dijkstra(&map, (0, 0), goal).map(|res| res.cost_of_path);
+            for algorithm in [Algorithm::Dijkstra, Algorithm::AStar] {
+                let res = graph
+                    .node(&format!("{},{}", goal.0, goal.1))
+                    .and_then(|goal| search::find_path(&graph, algorithm, start, goal));
+                assert_eq!(res.map(|res| res.cost_of_path), expected);
+            }
+        }
+    }
+
+    #[test]
+    fn test_parse_edge_list() {
+        let graph: Graph = "# a small graph\na b 4\nb c 1 # cheap\na b 2\n\nlonely\n"
+            .parse()
+            .unwrap();
+        assert_eq!(graph.len(), 4);
+        assert_eq!(graph.edge_count(), 2);
+        let (a, c) = (graph.node("a").unwrap(), graph.node("c").unwrap());
+        let res = graph.named(search::dijkstra(&graph, a, c).unwrap());
+        assert_eq!(res.path, ["a", "b", "c"]);
+        assert_eq!(res.cost_of_path, 3);
+        assert!(search::bfs(&graph, c, a).is_none());
+
+        assert!("a b".parse::<Graph>().is_err());
+        assert!("a b -1".parse::<Graph>().is_err());
+        let cost = usize::MAX / 2 + 1;
+        let huge = format!("a b {}\nb c {}", cost, cost);
+        assert!(huge.parse::<Graph>().is_err());
+        assert!(format!("a b {}", usize::MAX / 2).parse::<Graph>().is_ok());
+    }
+
+    #[test]
+    fn test_dot_export() {
+        let mut map = Map::new(3, 2);
+        map.grid[0][1] = MapNode::VerticalObstacle;
+        map.grid[1][2] = MapNode::Path(7);
+        let dot = map.to_dot();
+        assert!(dot.starts_with("digraph map {"));
+        assert_eq!(dot.matches("[pos=").count(), 5);
+        assert!(dot.contains("\"0,0\" [pos=\"0,0\", shape=box];"));
+        assert!(dot.contains("\"1,1\" -> \"1,2\" [label=\"7\"];"));
+        assert!(!dot.contains("weight"));
+        assert!(!dot.contains("\"0,1\""));
+    }
+}
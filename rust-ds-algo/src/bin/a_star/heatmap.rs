@@ -0,0 +1,260 @@
+use crate::search::{self, SearchSpace};
+use crate::{Algorithm, Map, MapNode, NeighborIterator};
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::io::IsTerminal;
+use std::str::FromStr;
+
+const RESET: &str = "\x1B[0m";
+
+/// How many times each cell was expanded.
+type Expansions = HashMap<(i32, i32), usize>;
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum ColorMode {
+    None,
+    Ansi256,
+    TrueColor,
+}
+
+impl ColorMode {
+    /// Guesses what the terminal on stdout supports from `NO_COLOR`, `COLORTERM` and `TERM`.
+    pub fn detect() -> Self {
+        let var = |name| std::env::var(name).unwrap_or_default();
+        if !std::io::stdout().is_terminal() || std::env::var_os("NO_COLOR").is_some() {
+            return ColorMode::None;
+        }
+        match (var("COLORTERM").as_str(), var("TERM")) {
+            ("truecolor" | "24bit", _) => ColorMode::TrueColor,
+            (_, term) if term.contains("256color") => ColorMode::Ansi256,
+            _ => ColorMode::None,
+        }
+    }
+
+    /// Escape sequence setting the background color.
+    fn background(self, (r, g, b): (u8, u8, u8)) -> String {
+        match self {
+            ColorMode::None => String::new(),
+            ColorMode::Ansi256 => {
+                let level = |c: u8| (c as u16 * 5 / 255) as u8;
+                let index = 16 + 36 * level(r) + 6 * level(g) + level(b);
+                format!("\x1B[48;5;{}m", index)
+            }
+            ColorMode::TrueColor => format!("\x1B[48;2;{};{};{}m", r, g, b),
+        }
+    }
+}
+
+impl FromStr for ColorMode {
+    type Err = String;
+
+    /// `auto` detects the mode, see `detect`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "auto" => Ok(ColorMode::detect()),
+            "never" => Ok(ColorMode::None),
+            "256" => Ok(ColorMode::Ansi256),
+            "truecolor" => Ok(ColorMode::TrueColor),
+            other => Err(format!("Unknown color mode: {}", other)),
+        }
+    }
+}
+
+/// Green for the cheapest tiles through yellow to red for the most expensive ones.
+fn gradient(t: f64) -> (u8, u8, u8) {
+    let lerp = |a: u8, b: u8, t: f64| (a as f64 + (b as f64 - a as f64) * t).round() as u8;
+    let (from, to, t) = if t < 0.5 {
+        ((46, 160, 67), (230, 200, 40), t * 2.0)
+    } else {
+        ((230, 200, 40), (200, 40, 40), t * 2.0 - 1.0)
+    };
+    (
+        lerp(from.0, to.0, t),
+        lerp(from.1, to.1, t),
+        lerp(from.2, to.2, t),
+    )
+}
+
+/// Wraps a map to count how often a search expands each cell, that is asks for its neighbors.
+/// Validating the found path asks the map directly, so it doesn't add to the counts.
+struct Counting<'a> {
+    map: &'a Map,
+    expansions: RefCell<Expansions>,
+}
+
+impl<'a> SearchSpace for Counting<'a> {
+    type Node = (i32, i32);
+    type Neighbors<'b>
+        = NeighborIterator<'b>
+    where
+        Self: 'b;
+
+    fn neighbors(&self, node: (i32, i32)) -> NeighborIterator<'_> {
+        *self.expansions.borrow_mut().entry(node).or_insert(0) += 1;
+        self.map.neighbors(node)
+    }
+
+    fn cost(&self, from: (i32, i32), to: (i32, i32)) -> usize {
+        self.map.cost(from, to)
+    }
+
+    fn heuristic(&self, a: (i32, i32), b: (i32, i32)) -> usize {
+        SearchSpace::heuristic(self.map, a, b)
+    }
+
+    fn cheapest_step(&self) -> usize {
+        self.map.cheapest_step()
+    }
+
+    fn cross_product(&self, start: (i32, i32), node: (i32, i32), goal: (i32, i32)) -> usize {
+        self.map.cross_product(start, node, goal)
+    }
+
+    fn is_passable(&self, node: (i32, i32)) -> bool {
+        self.map.is_passable(node)
+    }
+
+    fn is_adjacent(&self, from: (i32, i32), to: (i32, i32)) -> bool {
+        self.map.is_adjacent(from, to)
+    }
+}
+
+/// Tile costs as a colored gradient, optionally with the number of times a search expanded each
+/// cell written on top.
+pub struct Heatmap<'a> {
+    map: &'a Map,
+    mode: ColorMode,
+    expansions: Option<(Algorithm, Expansions)>,
+}
+
+impl<'a> Heatmap<'a> {
+    pub fn new(map: &'a Map, mode: ColorMode) -> Self {
+        Heatmap {
+            map,
+            mode,
+            expansions: None,
+        }
+    }
+
+    /// Runs `algorithm` from the agent to `goal` and overlays its expansion counts.
+    pub fn with_expansions(mut self, algorithm: Algorithm, goal: (i32, i32)) -> Self {
+        let counting = Counting {
+            map: self.map,
+            expansions: RefCell::new(HashMap::new()),
+        };
+        search::find_path(&counting, algorithm, self.map.agent_pos, goal);
+        self.expansions = Some((algorithm, counting.expansions.into_inner()));
+        self
+    }
+
+    fn max_cost(&self) -> i32 {
+        self.map
+            .grid
+            .iter()
+            .flatten()
+            .filter_map(|node| match node {
+                MapNode::Path(cost) => Some(*cost),
+                _ => None,
+            })
+            .max()
+            .unwrap_or(0)
+            .max(1)
+    }
+
+    fn color(&self, cost: i32) -> String {
+        self.mode
+            .background(gradient(cost.max(0) as f64 / self.max_cost() as f64))
+    }
+
+    /// The map with a legend below, or the plain rendering when colors are off.
+    pub fn render(&self) -> String {
+        if self.mode == ColorMode::None {
+            return self.map.render();
+        }
+
+        let mut text = self.map.render_with(|node, glyph| {
+            let cost = match self.map.tile(node) {
+                Some(MapNode::Path(cost)) => *cost,
+                _ => return glyph.to_string(),
+            };
+            let count = self
+                .expansions
+                .as_ref()
+                .and_then(|(_, counts)| counts.get(&node));
+            let glyph = match count {
+                Some(count) if *count < 10 => format!("\x1B[1;30m{}", count),
+                Some(_) => "\x1B[1;30m+".to_string(),
+                None => glyph.to_string(),
+            };
+            format!("{}{}{}", self.color(cost), glyph, RESET)
+        });
+
+        text.push_str("\n\nCost ");
+        let max_cost = self.max_cost();
+        let steps = max_cost.min(12);
+        for step in 0..=steps {
+            let cost = step * max_cost / steps;
+            text.push_str(&format!("{} {} {}", self.color(cost), cost, RESET));
+        }
+        if let Some((algorithm, counts)) = &self.expansions {
+            text.push_str(&format!(
+                "\nDigits: times {} expanded the cell (+ for 10 or more), {} expansions in total",
+                algorithm.title(),
+                counts.values().sum::<usize>()
+            ));
+        }
+        text
+    }
+
+    pub fn print(&self) {
+        println!("{}", self.render());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::heatmap::{ColorMode, Heatmap};
+    use crate::search;
+    use crate::{Algorithm, Map, MapNode};
+
+    fn ramp() -> Map {
+        let mut map = Map::new(5, 3);
+        for (r, row) in map.grid.iter_mut().enumerate() {
+            for (c, node) in row.iter_mut().enumerate() {
+                *node = MapNode::Path((r * 5 + c) as i32 % 12);
+            }
+        }
+        map
+    }
+
+    #[test]
+    fn test_no_color_falls_back_to_plain_render() {
+        let map = ramp();
+        let heatmap = Heatmap::new(&map, ColorMode::None).with_expansions(Algorithm::AStar, (2, 4));
+        assert_eq!(heatmap.render(), map.render());
+    }
+
+    #[test]
+    fn test_color_modes() {
+        let map = ramp();
+        let truecolor = Heatmap::new(&map, ColorMode::TrueColor).render();
+        assert!(truecolor.contains("\x1B[48;2;46;160;67m"));
+        assert!(truecolor.contains("\x1B[48;2;200;40;40m"));
+        assert!(truecolor.contains("Cost "));
+
+        let ansi = Heatmap::new(&map, ColorMode::Ansi256).render();
+        assert!(ansi.contains("\x1B[48;5;"));
+        assert!(!ansi.contains("\x1B[48;2;"));
+    }
+
+    #[test]
+    fn test_expansion_overlay_matches_search() {
+        let map = ramp();
+        let heatmap =
+            Heatmap::new(&map, ColorMode::TrueColor).with_expansions(Algorithm::Bfs, (2, 4));
+        let (_, counts) = heatmap.expansions.as_ref().unwrap();
+        let res = search::bfs(&map, (0, 0), (2, 4)).unwrap();
+        assert_eq!(counts.values().sum::<usize>(), res.nodes_expanded);
+        assert!(heatmap.render().contains("expanded the cell"));
+    }
+}
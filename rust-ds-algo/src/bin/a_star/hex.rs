@@ -0,0 +1,210 @@
+use crate::search::{self, SearchSpace};
+use crate::{Map, MapNode};
+use serde::{Deserialize, Serialize};
+
+const DIRECTIONS: [(i32, i32); 6] = [(1, 0), (1, -1), (0, -1), (-1, 0), (-1, 1), (0, 1)];
+
+/// Axial hex coordinates, see https://www.redblobgames.com/grids/hexagons/.
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash, Ord, PartialOrd, Serialize, Deserialize)]
+pub struct Axial {
+    pub q: i32,
+    pub r: i32,
+}
+
+impl Axial {
+    /// Converts a (row, col) cell of an "odd-r" layout, where odd rows are shoved right by half a
+    /// tile, into axial coordinates.
+    pub fn from_offset(node: (i32, i32)) -> Self {
+        Axial {
+            q: node.1 - (node.0 - (node.0 & 1)) / 2,
+            r: node.0,
+        }
+    }
+
+    pub fn to_offset(self) -> (i32, i32) {
+        (self.r, self.q + (self.r - (self.r & 1)) / 2)
+    }
+
+    pub fn distance(self, other: Axial) -> usize {
+        let dq = self.q - other.q;
+        let dr = self.r - other.r;
+        ((dq.abs() + dr.abs() + (dq + dr).abs()) / 2) as usize
+    }
+}
+
+/// A hex tile map. Tiles are stored in a regular `Map` in odd-r offset layout, so maps can be
+/// generated, parsed and edited the same way as square grids.
+#[derive(Clone, Debug)]
+pub struct HexMap {
+    map: Map,
+}
+
+impl HexMap {
+    pub fn new(map: Map) -> Self {
+        HexMap { map }
+    }
+
+    pub fn agent_pos(&self) -> Axial {
+        Axial::from_offset(self.map.agent_pos)
+    }
+
+    pub fn draw(&mut self, node: Axial, char: &str) {
+        self.map.draw(node.to_offset(), char);
+    }
+
+    pub fn render(&self) -> String {
+        let label_width = self.map.height.to_string().len();
+        let mut text = String::new();
+        for row in 0..self.map.height as i32 {
+            if row != 0 {
+                text.push('\n');
+            }
+            text.push_str(&format!("{:>width$} | ", row, width = label_width));
+            if row & 1 == 1 {
+                text.push_str("  ");
+            }
+            for col in 0..self.map.width as i32 {
+                if col != 0 {
+                    text.push_str("   ");
+                }
+                text.push_str(self.map.glyph((row, col)));
+            }
+        }
+        text
+    }
+}
+
+pub struct HexNeighbors<'a> {
+    map: &'a Map,
+    center: Axial,
+    direction: usize,
+}
+
+impl<'a> Iterator for HexNeighbors<'a> {
+    type Item = Axial;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while let Some((dq, dr)) = DIRECTIONS.get(self.direction) {
+            self.direction += 1;
+            let neighbor = Axial {
+                q: self.center.q + dq,
+                r: self.center.r + dr,
+            };
+            if self.map.is_walkable(neighbor.to_offset()) {
+                return Some(neighbor);
+            }
+        }
+        None
+    }
+}
+
+impl SearchSpace for HexMap {
+    type Node = Axial;
+    type Neighbors<'a> = HexNeighbors<'a>;
+
+    fn neighbors(&self, node: Axial) -> HexNeighbors<'_> {
+        HexNeighbors {
+            map: &self.map,
+            center: node,
+            direction: 0,
+        }
+    }
+
+    fn cost(&self, _from: Axial, to: Axial) -> usize {
+        match self.map.tile(to.to_offset()) {
+            Some(MapNode::Path(cost)) => *cost as usize,
+            _ => usize::MAX,
+        }
+    }
+
+    fn heuristic(&self, a: Axial, b: Axial) -> usize {
+        a.distance(b)
+    }
+
+    fn cheapest_step(&self) -> usize {
+        self.map.cheapest_step()
+    }
+
+    fn cross_product(&self, start: Axial, node: Axial, goal: Axial) -> usize {
+        search::planar_cross_product((start.q, start.r), (node.q, node.r), (goal.q, goal.r))
+    }
+
+    fn is_passable(&self, node: Axial) -> bool {
+        self.map.is_walkable(node.to_offset())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::hex::{Axial, HexMap};
+    use crate::search::{self, SearchSpace};
+    use crate::{Algorithm, Map, MapNode};
+
+    fn open_map(width: usize, height: usize) -> Map {
+        let mut map = Map::new(width, height);
+        for row in &mut map.grid {
+            for node in row.iter_mut() {
+                *node = MapNode::Path(1);
+            }
+        }
+        map
+    }
+
+    #[test]
+    fn test_offset_round_trip() {
+        for row in -3..4 {
+            for col in -3..4 {
+                assert_eq!(Axial::from_offset((row, col)).to_offset(), (row, col));
+            }
+        }
+    }
+
+    #[test]
+    fn test_neighbors() {
+        let hex = HexMap::new(open_map(5, 5));
+        let center = Axial::from_offset((2, 2));
+        let neighbors = hex.neighbors(center).collect::<Vec<_>>();
+
+        assert_eq!(neighbors.len(), 6);
+        assert!(neighbors.iter().all(|n| center.distance(*n) == 1));
+        assert_eq!(hex.neighbors(Axial::from_offset((0, 0))).count(), 2);
+    }
+
+    #[test]
+    fn test_all_algorithms_find_shortest_hex_path() {
+        let mut map = open_map(6, 6);
+        map.grid[2][1] = MapNode::VerticalObstacle;
+        let hex = HexMap::new(map);
+        let goal = Axial::from_offset((4, 4));
+
+        for algorithm in Algorithm::ALL {
+            let res = search::find_path(&hex, algorithm, hex.agent_pos(), goal).unwrap();
+            assert_eq!(res.path.first(), Some(&hex.agent_pos()));
+            assert_eq!(res.path.last(), Some(&goal));
+            assert!(res.path.windows(2).all(|w| w[0].distance(w[1]) == 1));
+            if algorithm != Algorithm::Gbfs {
+                assert_eq!(res.cost_of_path, hex.agent_pos().distance(goal));
+            }
+        }
+    }
+
+    #[test]
+    fn test_optimal_searches_handle_free_tiles() {
+        // The direct route costs 1, the detour around the wall is free.
+        let mut map = Map::new(3, 3);
+        map.grid[0][1] = MapNode::Path(1);
+        map.grid[1][1] = MapNode::VerticalObstacle;
+        let hex = HexMap::new(map);
+        let goal = Axial::from_offset((0, 2));
+
+        for algorithm in [
+            Algorithm::Dijkstra,
+            Algorithm::AStar,
+            Algorithm::IdaStar,
+            Algorithm::Fringe,
+        ] {
+            let res = search::find_path(&hex, algorithm, hex.agent_pos(), goal).unwrap();
+            assert_eq!(res.cost_of_path, 0, "{:?}", algorithm);
+        }
+    }
+}
@@ -0,0 +1,221 @@
+use crate::search::SearchSpace;
+use crate::PathfindingResult;
+use std::collections::{HashMap, VecDeque};
+
+/// Iterative deepening A*: depth first searches that give up on nodes whose estimated total
+/// cost exceeds a threshold, raised to the smallest exceeding estimate after every round. Only
+/// the current path and its neighbor iterators are kept, so `nodes_visited` is the longest path
+/// held at once rather than the number of distinct nodes seen. Nodes reached over many paths
+/// are expanded once per path and round, which `nodes_expanded` counts.
+pub fn ida_star<S: SearchSpace>(
+    space: &S,
+    start: S::Node,
+    goal: S::Node,
+) -> Option<PathfindingResult<S::Node>> {
+    let cheapest_step = space.cheapest_step();
+    let heuristic = |node| space.heuristic(goal, node) * cheapest_step;
+    let mut threshold = heuristic(start);
+    let (mut expanded, mut deepest) = (0, 1);
+    if start == goal {
+        return Some(PathfindingResult {
+            nodes_visited: 1,
+            nodes_expanded: 0,
+            cost_of_path: 0,
+            path: vec![start],
+        });
+    }
+
+    loop {
+        let mut path = vec![start];
+        let mut costs = vec![0];
+        let mut stack = vec![space.neighbors(start)];
+        let mut next_threshold = usize::MAX;
+        expanded += 1;
+
+        while let Some(neighbors) = stack.last_mut() {
+            let node = path[path.len() - 1];
+            let neighbor = match neighbors.next() {
+                Some(neighbor) if !path.contains(&neighbor) => neighbor,
+                Some(_) => continue,
+                None => {
+                    stack.pop();
+                    path.pop();
+                    costs.pop();
+                    continue;
+                }
+            };
+
+            let cost = costs[costs.len() - 1] + space.cost(node, neighbor);
+            let estimate = cost.saturating_add(heuristic(neighbor));
+            if estimate > threshold {
+                next_threshold = next_threshold.min(estimate);
+                continue;
+            }
+            path.push(neighbor);
+            costs.push(cost);
+            deepest = deepest.max(path.len());
+            if neighbor == goal {
+                return Some(PathfindingResult {
+                    nodes_visited: deepest,
+                    nodes_expanded: expanded,
+                    cost_of_path: cost,
+                    path,
+                });
+            }
+            expanded += 1;
+            stack.push(space.neighbors(neighbor));
+        }
+
+        if next_threshold == usize::MAX {
+            return None;
+        }
+        threshold = next_threshold;
+    }
+}
+
+/// Fringe search: the rounds of `ida_star`, but nodes over the threshold are kept on a list for
+/// the next round instead of being found again from the start, and the cheapest known cost of
+/// every node is cached so that no path is followed twice. That cache grows like the one of A*,
+/// so unlike `ida_star` this saves no memory. What it saves is the priority queue, as both lists
+/// are plain queues.
+pub fn fringe<S: SearchSpace>(
+    space: &S,
+    start: S::Node,
+    goal: S::Node,
+) -> Option<PathfindingResult<S::Node>> {
+    let mut cache: HashMap<S::Node, (usize, S::Node)> = HashMap::new();
+    let mut now = VecDeque::new();
+    let mut later = VecDeque::new();
+    let cheapest_step = space.cheapest_step();
+    let heuristic = |node| space.heuristic(goal, node) * cheapest_step;
+    let mut threshold = heuristic(start);
+    let mut expanded = 0;
+    cache.insert(start, (0, start));
+    now.push_back((start, 0));
+
+    while !now.is_empty() {
+        let mut next_threshold = usize::MAX;
+        while let Some((node, cost)) = now.pop_front() {
+            if cost > cache[&node].0 {
+                continue;
+            }
+            let estimate = cost.saturating_add(heuristic(node));
+            if estimate > threshold {
+                next_threshold = next_threshold.min(estimate);
+                later.push_back((node, cost));
+                continue;
+            }
+            if node == goal {
+                return fringe_path(&cache, start, goal, expanded);
+            }
+            expanded += 1;
+
+            for neighbor in space.neighbors(node) {
+                let cost = cost + space.cost(node, neighbor);
+                if cache
+                    .get(&neighbor)
+                    .is_some_and(|&(known, _)| known <= cost)
+                {
+                    continue;
+                }
+                cache.insert(neighbor, (cost, node));
+                now.push_front((neighbor, cost));
+            }
+        }
+        threshold = next_threshold;
+        std::mem::swap(&mut now, &mut later);
+    }
+
+    None
+}
+
+fn fringe_path<N: Copy + Eq + std::hash::Hash>(
+    cache: &HashMap<N, (usize, N)>,
+    start: N,
+    goal: N,
+    expanded: usize,
+) -> Option<PathfindingResult<N>> {
+    let mut path = vec![goal];
+    let mut current = goal;
+    while current != start {
+        current = cache[&current].1;
+        path.push(current);
+    }
+    path.reverse();
+
+    Some(PathfindingResult {
+        nodes_visited: cache.len(),
+        nodes_expanded: expanded,
+        cost_of_path: cache[&goal].0,
+        path,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::ida;
+    use crate::search;
+    use crate::{Map, MapNode};
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+
+    /// A map with tile costs of at least 1, on which the Manhattan distance never overestimates.
+    /// The top row and columns 7 and 9 are kept open, so that the goals of the tests below are
+    /// always reachable.
+    fn admissible_map(seed: u64) -> Map {
+        let mut map = Map::new(10, 10);
+        map.generate_map(&mut StdRng::seed_from_u64(seed), (5, 15));
+        for (row, tiles) in map.grid.iter_mut().enumerate() {
+            for (col, node) in tiles.iter_mut().enumerate() {
+                match node {
+                    MapNode::Path(cost) => *cost = (*cost % 3).max(1),
+                    _ if row == 0 || col == 7 || col == 9 => *node = MapNode::Path(1),
+                    _ => (),
+                }
+            }
+        }
+        map
+    }
+
+    #[test]
+    fn test_costs_match_a_star() {
+        for seed in 0..5 {
+            let map = admissible_map(seed);
+            for goal in [(9, 9), (4, 7), (0, 0)] {
+                let expected = search::a_star(&map, (0, 0), goal).unwrap();
+                for res in [
+                    ida::ida_star(&map, (0, 0), goal),
+                    ida::fringe(&map, (0, 0), goal),
+                ] {
+                    let res = res.unwrap();
+                    assert_eq!(
+                        res.cost_of_path, expected.cost_of_path,
+                        "seed {} goal {:?}",
+                        seed, goal
+                    );
+                    assert_eq!(res.validate(&map, (0, 0), goal), Ok(()));
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_ida_star_only_holds_the_path() {
+        let map = admissible_map(2);
+        let a_star = search::a_star(&map, (0, 0), (9, 9)).expect("the fixture has a path");
+        let ida = ida::ida_star(&map, (0, 0), (9, 9)).unwrap();
+        assert!(ida.nodes_visited >= ida.path.len());
+        assert!(ida.nodes_visited < a_star.nodes_visited);
+        assert!(ida.nodes_expanded >= a_star.nodes_expanded);
+    }
+
+    #[test]
+    fn test_walled_off_goal() {
+        // Every simple path is tried before giving up, so keep the map small.
+        let mut map = Map::new(4, 4);
+        map.grid[2][3] = MapNode::VerticalObstacle;
+        map.grid[3][2] = MapNode::VerticalObstacle;
+        assert!(ida::ida_star(&map, (0, 0), (3, 3)).is_none());
+        assert!(ida::fringe(&map, (0, 0), (3, 3)).is_none());
+    }
+}
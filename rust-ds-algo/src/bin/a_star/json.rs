@@ -0,0 +1,252 @@
+use crate::{Map, MapNode};
+use serde::{Deserialize, Serialize};
+use std::convert::TryFrom;
+use std::ops::RangeInclusive;
+
+/// A start/goal pair, as stored in query files and fixtures.
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Serialize, Deserialize)]
+pub struct Query<N = (i32, i32)> {
+    pub start: N,
+    pub goal: N,
+}
+
+/// A map as it comes off the wire, before its layout has been checked.
+#[derive(Deserialize)]
+pub struct MapData {
+    width: usize,
+    height: usize,
+    grid: Vec<Vec<MapNode>>,
+    agent_pos: (i32, i32),
+}
+
+impl TryFrom<MapData> for Map {
+    type Error = String;
+
+    fn try_from(data: MapData) -> Result<Self, Self::Error> {
+        if data.grid.len() != data.height {
+            return Err(format!(
+                "Map has {} rows, expected a height of {}",
+                data.grid.len(),
+                data.height
+            ));
+        }
+        if let Some(row) = data.grid.iter().position(|row| row.len() != data.width) {
+            return Err(format!("Row {} does not have {} tiles", row, data.width));
+        }
+        for (row, tiles) in data.grid.iter().enumerate() {
+            for (col, tile) in tiles.iter().enumerate() {
+                match tile {
+                    MapNode::Path(cost) if *cost < 0 => {
+                        return Err(format!("Negative cost {} at ({}, {})", cost, row, col));
+                    }
+                    MapNode::Custom(glyph) if !is_single_cell(glyph) => {
+                        return Err(format!(
+                            "Custom glyph {:?} at ({}, {}) is not one printable column wide",
+                            glyph, row, col
+                        ));
+                    }
+                    _ => (),
+                }
+            }
+        }
+
+        let map = Map {
+            width: data.width,
+            height: data.height,
+            grid: data.grid,
+            agent_pos: data.agent_pos,
+        };
+        if !map.contains(map.agent_pos) {
+            return Err(format!("Agent {:?} lies outside the map", map.agent_pos));
+        }
+        Ok(map)
+    }
+}
+
+/// Characters that terminals draw two columns wide: East Asian wide and fullwidth forms, and
+/// emoji that are shown as pictures by default.
+const DOUBLE_WIDTH: &[RangeInclusive<char>] = &[
+    '\u{1100}'..='\u{115F}',
+    '\u{231A}'..='\u{231B}',
+    '\u{2329}'..='\u{232A}',
+    '\u{23E9}'..='\u{23EC}',
+    '\u{23F0}'..='\u{23F0}',
+    '\u{23F3}'..='\u{23F3}',
+    '\u{25FD}'..='\u{25FE}',
+    '\u{2614}'..='\u{2615}',
+    '\u{2648}'..='\u{2653}',
+    '\u{267F}'..='\u{267F}',
+    '\u{2693}'..='\u{2693}',
+    '\u{26A1}'..='\u{26A1}',
+    '\u{26AA}'..='\u{26AB}',
+    '\u{26BD}'..='\u{26BE}',
+    '\u{26C4}'..='\u{26C5}',
+    '\u{26CE}'..='\u{26CE}',
+    '\u{26D4}'..='\u{26D4}',
+    '\u{26EA}'..='\u{26EA}',
+    '\u{26F2}'..='\u{26F3}',
+    '\u{26F5}'..='\u{26F5}',
+    '\u{26FA}'..='\u{26FA}',
+    '\u{26FD}'..='\u{26FD}',
+    '\u{2705}'..='\u{2705}',
+    '\u{270A}'..='\u{270B}',
+    '\u{2728}'..='\u{2728}',
+    '\u{274C}'..='\u{274C}',
+    '\u{274E}'..='\u{274E}',
+    '\u{2753}'..='\u{2755}',
+    '\u{2757}'..='\u{2757}',
+    '\u{2795}'..='\u{2797}',
+    '\u{27B0}'..='\u{27B0}',
+    '\u{27BF}'..='\u{27BF}',
+    '\u{2B1B}'..='\u{2B1C}',
+    '\u{2B50}'..='\u{2B50}',
+    '\u{2B55}'..='\u{2B55}',
+    '\u{2E80}'..='\u{303E}',
+    '\u{3041}'..='\u{33FF}',
+    '\u{3400}'..='\u{4DBF}',
+    '\u{4E00}'..='\u{9FFF}',
+    '\u{A000}'..='\u{A4CF}',
+    '\u{A960}'..='\u{A97F}',
+    '\u{AC00}'..='\u{D7A3}',
+    '\u{F900}'..='\u{FAFF}',
+    '\u{FE10}'..='\u{FE19}',
+    '\u{FE30}'..='\u{FE6F}',
+    '\u{FF00}'..='\u{FF60}',
+    '\u{FFE0}'..='\u{FFE6}',
+    '\u{1B000}'..='\u{1B2FF}',
+    '\u{1F004}'..='\u{1F004}',
+    '\u{1F0CF}'..='\u{1F0CF}',
+    '\u{1F18E}'..='\u{1F18E}',
+    '\u{1F191}'..='\u{1F19A}',
+    '\u{1F200}'..='\u{1F251}',
+    '\u{1F300}'..='\u{1F64F}',
+    '\u{1F680}'..='\u{1F6FF}',
+    '\u{1F7E0}'..='\u{1F7EB}',
+    '\u{1F90C}'..='\u{1F9FF}',
+    '\u{1FA70}'..='\u{1FAFF}',
+    '\u{20000}'..='\u{2FFFD}',
+    '\u{30000}'..='\u{3FFFD}',
+];
+
+/// Characters that take no column of their own: combining marks, zero width spaces and joiners,
+/// and variation selectors.
+const ZERO_WIDTH: &[RangeInclusive<char>] = &[
+    '\u{00AD}'..='\u{00AD}',
+    '\u{0300}'..='\u{036F}',
+    '\u{0483}'..='\u{0489}',
+    '\u{1AB0}'..='\u{1AFF}',
+    '\u{1DC0}'..='\u{1DFF}',
+    '\u{200B}'..='\u{200F}',
+    '\u{2028}'..='\u{202E}',
+    '\u{2060}'..='\u{206F}',
+    '\u{20D0}'..='\u{20FF}',
+    '\u{FE00}'..='\u{FE0F}',
+    '\u{FE20}'..='\u{FE2F}',
+    '\u{FEFF}'..='\u{FEFF}',
+    '\u{E0100}'..='\u{E01EF}',
+];
+
+/// Every tile is rendered as exactly one column, anything else shifts the rest of its row.
+fn is_single_cell(glyph: &str) -> bool {
+    let mut chars = glyph.chars();
+    match (chars.next(), chars.next()) {
+        (Some(c), None) => {
+            !c.is_control()
+                && !c.is_whitespace()
+                && !DOUBLE_WIDTH
+                    .iter()
+                    .chain(ZERO_WIDTH)
+                    .any(|range| range.contains(&c))
+        }
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::json::Query;
+    use crate::search;
+    use crate::{Map, MapNode, PathfindingResult};
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+
+    #[test]
+    fn test_map_round_trip() {
+        let mut map = Map::new(12, 8);
+        map.generate_map(&mut StdRng::seed_from_u64(3), (5, 10));
+        map.grid[2][3] = MapNode::Custom("☺".to_string());
+        map.agent_pos = (1, 2);
+
+        let json = serde_json::to_string(&map).unwrap();
+        let parsed: Map = serde_json::from_str(&json).unwrap();
+        assert_eq!(parsed.render(), map.render());
+        assert_eq!(parsed.agent_pos, (1, 2));
+    }
+
+    #[test]
+    fn test_invalid_maps_are_rejected() {
+        let map = |grid: &str, agent: &str| {
+            serde_json::from_str::<Map>(&format!(
+                r#"{{"width":2,"height":2,"grid":{},"agent_pos":{}}}"#,
+                grid, agent
+            ))
+        };
+
+        assert!(map(
+            r#"[[{"Path":1},{"Path":1}],[{"Path":1},"VerticalObstacle"]]"#,
+            "[0,0]"
+        )
+        .is_ok());
+        assert!(map(r#"[[{"Path":1},{"Path":1}]]"#, "[0,0]").is_err());
+        assert!(map(r#"[[{"Path":1}],[{"Path":1},{"Path":1}]]"#, "[0,0]").is_err());
+        assert!(map(
+            r#"[[{"Path":1},{"Path":1}],[{"Path":1},{"Path":1}]]"#,
+            "[2,0]"
+        )
+        .is_err());
+        assert!(map(
+            r#"[[{"Path":1},{"Path":-2}],[{"Path":1},{"Path":1}]]"#,
+            "[0,0]"
+        )
+        .is_err());
+        for glyph in ["x", "é", "☺", "█"] {
+            let grid = format!(
+                r#"[[{{"Path":1}},{{"Custom":{}}}],[{{"Path":1}},{{"Path":1}}]]"#,
+                serde_json::to_string(glyph).unwrap()
+            );
+            assert!(map(&grid, "[0,0]").is_ok(), "{:?} was rejected", glyph);
+        }
+        for glyph in [
+            "", "ab", "\n", " ", "\u{1b}", "字", "Ａ", "🙂", "⚡", "\u{301}", "\u{200d}",
+        ] {
+            let grid = format!(
+                r#"[[{{"Path":1}},{{"Custom":{}}}],[{{"Path":1}},{{"Path":1}}]]"#,
+                serde_json::to_string(glyph).unwrap()
+            );
+            assert!(map(&grid, "[0,0]").is_err(), "{:?} was accepted", glyph);
+        }
+    }
+
+    #[test]
+    fn test_result_and_query_round_trip() {
+        let mut map = Map::new(6, 6);
+        for (r, row) in map.grid.iter_mut().enumerate() {
+            for (c, node) in row.iter_mut().enumerate() {
+                *node = MapNode::Path((r * c % 7) as i32);
+            }
+        }
+        let query = Query {
+            start: (0, 0),
+            goal: (5, 5),
+        };
+        let result = search::a_star(&map, query.start, query.goal).unwrap();
+
+        let json = serde_json::to_string(&(query, &result)).unwrap();
+        let (parsed_query, parsed): (Query, PathfindingResult) =
+            serde_json::from_str(&json).unwrap();
+        assert_eq!(parsed_query, query);
+        assert_eq!(parsed.path, result.path);
+        assert_eq!(parsed.cost_of_path, result.cost_of_path);
+        assert_eq!(parsed.nodes_visited, result.nodes_visited);
+    }
+}
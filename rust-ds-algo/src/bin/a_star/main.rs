@@ -0,0 +1,1113 @@
+use itertools::Itertools;
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+use std::str::FromStr;
+use std::time::{Duration, Instant};
+
+mod batch;
+mod bench;
+mod ch;
+mod cli;
+mod editor;
+mod fog;
+mod frontier;
+mod graph;
+mod heatmap;
+mod hex;
+mod ida;
+mod json;
+mod quadtree;
+mod reach;
+mod search;
+mod simulation;
+mod space_time;
+mod turns;
+mod validate;
+mod voxel;
+mod yen;
+
+use ch::ContractionHierarchy;
+use cli::{Format, Options, Topology};
+use editor::Editor;
+use graph::Graph;
+use heatmap::{ColorMode, Heatmap};
+use hex::{Axial, HexMap};
+use quadtree::Clearance;
+use search::SearchSpace;
+use simulation::{Event, Simulation};
+use space_time::{ObstacleSchedule, Patrol};
+use turns::Vehicle;
+use voxel::{Voxel, VoxelMap};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PathfindingResult<N = (i32, i32)> {
+    nodes_visited: usize,
+    nodes_expanded: usize,
+    /// Sum of the costs of the steps along `path`. The start node is not counted.
+    cost_of_path: usize,
+    path: Vec<N>,
+}
+
+pub struct NeighborIterator<'a> {
+    mid: (i32, i32),
+    visited: u8,
+    grid: &'a Vec<Vec<MapNode>>,
+    size: (usize, usize),
+}
+
+impl<'a> NeighborIterator<'a> {
+    pub fn new(mid: (i32, i32), grid: &'a Vec<Vec<MapNode>>, size: (usize, usize)) -> Self {
+        NeighborIterator {
+            mid,
+            visited: 0,
+            grid,
+            size,
+        }
+    }
+}
+
+impl<'a> Iterator for NeighborIterator<'a> {
+    type Item = (i32, i32);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let east = (self.mid.0, self.mid.1 + 1);
+        let north = (self.mid.0 - 1, self.mid.1);
+        let west = (self.mid.0, self.mid.1 - 1);
+        let south = (self.mid.0 + 1, self.mid.1);
+
+        while self.visited < 4 {
+            let current = match self.visited {
+                0 => {
+                    if east.1 >= self.size.1 as i32 {
+                        self.visited += 1;
+                        continue;
+                    } else {
+                        east
+                    }
+                }
+                1 => {
+                    if north.0 < 0 {
+                        self.visited += 1;
+                        continue;
+                    } else {
+                        north
+                    }
+                }
+                2 => {
+                    if west.1 < 0 {
+                        self.visited += 1;
+                        continue;
+                    } else {
+                        west
+                    }
+                }
+                3 => {
+                    if south.0 >= self.size.0 as i32 {
+                        self.visited += 1;
+                        continue;
+                    } else {
+                        south
+                    }
+                }
+                _ => return None,
+            };
+
+            match self
+                .grid
+                .get(current.0 as usize)
+                .and_then(|col| col.get(current.1 as usize))
+            {
+                Some(MapNode::Path(_)) => {
+                    self.visited += 1;
+                    return Some(current);
+                }
+                _ => {
+                    self.visited += 1;
+                }
+            }
+        }
+
+        None
+    }
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub enum MapNode {
+    VerticalObstacle,
+    HorizontalObstacle,
+    Path(i32),
+    Custom(String),
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Hash, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Algorithm {
+    Bfs,
+    Dijkstra,
+    Gbfs,
+    AStar,
+    IdaStar,
+    Fringe,
+}
+
+impl Algorithm {
+    pub const ALL: [Algorithm; 6] = [
+        Algorithm::Bfs,
+        Algorithm::Dijkstra,
+        Algorithm::Gbfs,
+        Algorithm::AStar,
+        Algorithm::IdaStar,
+        Algorithm::Fringe,
+    ];
+
+    /// The algorithms run unless asked otherwise. IDA* tries every path within its threshold,
+    /// which takes ages on larger maps, and fringe search repeats its rounds much like it, so
+    /// both have to be picked explicitly.
+    pub const DEFAULT: [Algorithm; 4] = [
+        Algorithm::Bfs,
+        Algorithm::Dijkstra,
+        Algorithm::Gbfs,
+        Algorithm::AStar,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Algorithm::Bfs => "bfs",
+            Algorithm::Dijkstra => "dijkstra",
+            Algorithm::Gbfs => "gbfs",
+            Algorithm::AStar => "astar",
+            Algorithm::IdaStar => "idastar",
+            Algorithm::Fringe => "fringe",
+        }
+    }
+
+    pub fn title(&self) -> &'static str {
+        match self {
+            Algorithm::Bfs => "Breadth First Search",
+            Algorithm::Dijkstra => "Dijkstra",
+            Algorithm::Gbfs => "Greedy Best First Search",
+            Algorithm::AStar => "A*",
+            Algorithm::IdaStar => "IDA*",
+            Algorithm::Fringe => "Fringe Search",
+        }
+    }
+}
+
+impl FromStr for Algorithm {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Algorithm::ALL
+            .iter()
+            .find(|algorithm| algorithm.name() == s.to_lowercase())
+            .copied()
+            .ok_or(format!("Unknown algorithm: {}", s))
+    }
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(try_from = "json::MapData")]
+struct Map {
+    width: usize,
+    height: usize,
+    grid: Vec<Vec<MapNode>>,
+    agent_pos: (i32, i32),
+}
+
+impl Map {
+    pub fn new(width: usize, height: usize) -> Self {
+        let vec = vec![vec![MapNode::Path(0); width]; height];
+        Map {
+            width,
+            height,
+            grid: vec,
+            agent_pos: (0, 0),
+        }
+    }
+
+    /// Randomises the tile costs and places a random number of obstacles from the
+    /// `obstacle_num` range. Returns how many obstacles were placed, which is fewer than asked
+    /// for when the map fills up first.
+    pub fn generate_map<R: Rng>(&mut self, rng: &mut R, obstacle_num: (usize, usize)) -> usize {
+        let obstacle_num = rng.gen_range(obstacle_num.0..obstacle_num.1);
+
+        for row in &mut self.grid {
+            for node in row.iter_mut() {
+                *node = MapNode::Path(rng.gen_range(0..12));
+            }
+        }
+
+        // Obstacles can only start on a free tile, so give up once none are left.
+        let mut free_tiles = self.width * self.height;
+        let mut placed_obstacle = 0;
+        while placed_obstacle != obstacle_num && free_tiles > 0 {
+            let is_horizontal = rng.gen_bool(0.5);
+            let length = match is_horizontal {
+                true => rng.gen_range(self.width / 10..(self.width / 3).max(self.width / 10 + 1)),
+                false => {
+                    rng.gen_range(self.height / 10..(self.height / 3).max(self.height / 10 + 1))
+                }
+            };
+            let row = rng.gen_range(0..self.height);
+            let col = rng.gen_range(0..self.width);
+            if let Some(MapNode::Path(_)) = self.grid.get(row).and_then(|row| row.get(col)) {
+                if is_horizontal {
+                    let length = if col + length > self.width - 1 {
+                        self.width
+                    } else {
+                        col + length
+                    };
+                    for l in col..length {
+                        if l <= self.width {
+                            let grid_row = self.grid.get_mut(row).unwrap();
+                            if let MapNode::Path(_) = grid_row.remove(l) {
+                                free_tiles -= 1;
+                            }
+                            grid_row.insert(l, MapNode::HorizontalObstacle);
+                        }
+                    }
+                } else {
+                    let length = if row + length > self.height - 1 {
+                        self.height
+                    } else {
+                        row + length
+                    };
+                    for l in row..length {
+                        if l <= self.height {
+                            let grid_row = self.grid.get_mut(l).unwrap();
+                            if let MapNode::Path(_) = grid_row.remove(col) {
+                                free_tiles -= 1;
+                            }
+                            grid_row.insert(col, MapNode::VerticalObstacle);
+                        }
+                    }
+                }
+                placed_obstacle += 1;
+            }
+        }
+
+        placed_obstacle
+    }
+
+    pub fn contains(&self, node: (i32, i32)) -> bool {
+        node.0 >= 0
+            && node.1 >= 0
+            && (node.0 as usize) < self.height
+            && (node.1 as usize) < self.width
+    }
+
+    pub fn print(&self) {
+        println!("{}", self.render());
+    }
+
+    /// Prints the map with the cells `highlight` picks out in reverse video.
+    pub fn print_highlighted(&self, highlight: impl Fn((i32, i32)) -> bool) {
+        println!("{}", self.render_highlighted(highlight));
+    }
+
+    pub fn render(&self) -> String {
+        self.render_with(|_, glyph| glyph.to_string())
+    }
+
+    pub fn render_highlighted(&self, highlight: impl Fn((i32, i32)) -> bool) -> String {
+        self.render_with(|node, glyph| {
+            if highlight(node) {
+                format!("\x1B[7m{}\x1B[0m", glyph)
+            } else {
+                glyph.to_string()
+            }
+        })
+    }
+
+    /// Renders the map, passing each cell's glyph through `style` first.
+    pub fn render_with(&self, style: impl Fn((i32, i32), &str) -> String) -> String {
+        let horizontal_space = self.width.to_string().len();
+        let mut map = String::new();
+        map.push_str(&" ".repeat(self.height.to_string().len() + 3));
+        map.push_str(
+            &(0..self.width)
+                .map(|n| {
+                    format!(
+                        "{}{}",
+                        n,
+                        " ".repeat(horizontal_space - n.to_string().len() + 1)
+                    )
+                })
+                .format(" ")
+                .to_string(),
+        );
+        map.push('\n');
+        map.push_str(&" ".repeat(self.height.to_string().len() + 2));
+        map.push_str(
+            &(0..self.width)
+                .map(|_| "┈".repeat(horizontal_space))
+                .format(&" ".repeat(horizontal_space))
+                .to_string(),
+        );
+        map.push('\n');
+        for (i, row) in self.grid.iter().enumerate() {
+            if i != 0 {
+                map.push('\n');
+            }
+            let space = self.height.to_string().len() - i.to_string().len();
+            map.push_str(&format!("{}{}| ", i, " ".repeat(space + 1)));
+            for j in 0..row.len() {
+                let node = (i as i32, j as i32);
+                map.push_str(&style(node, self.glyph(node)));
+                if j != row.len() - 1 {
+                    map.push_str(&" ".repeat(horizontal_space + 1));
+                }
+            }
+        }
+
+        map
+    }
+
+    pub fn tile(&self, node: (i32, i32)) -> Option<&MapNode> {
+        if !self.contains(node) {
+            return None;
+        }
+        self.grid
+            .get(node.0 as usize)
+            .and_then(|row| row.get(node.1 as usize))
+    }
+
+    pub fn is_walkable(&self, node: (i32, i32)) -> bool {
+        matches!(self.tile(node), Some(MapNode::Path(_)))
+    }
+
+    pub fn glyph(&self, node: (i32, i32)) -> &str {
+        match self.tile(node) {
+            Some(MapNode::VerticalObstacle) => "┃",
+            Some(MapNode::HorizontalObstacle) => "━",
+            Some(MapNode::Path(cost)) => {
+                if self.agent_pos == node {
+                    "╳"
+                } else if *cost <= 5 {
+                    "·"
+                } else {
+                    "☷"
+                }
+            }
+            Some(MapNode::Custom(char)) => char.as_str(),
+            None => " ",
+        }
+    }
+
+    pub fn neighbors(&self, node: (i32, i32)) -> NeighborIterator<'_> {
+        NeighborIterator::new(node, &self.grid, (self.height, self.width))
+    }
+
+    pub fn draw(&mut self, node: (i32, i32), char: &str) {
+        if let Some(col) = self.grid.get_mut(node.0 as usize) {
+            col.remove(node.1 as usize);
+            col.insert(node.1 as usize, MapNode::Custom(char.to_string()));
+        };
+    }
+
+    /// Animates the agent walking `path`, leaving a trail behind, with the obstacles of
+    /// `schedule` drawn where they are at each tick.
+    pub fn render_path(
+        &self,
+        title: &str,
+        path: &[(i32, i32)],
+        schedule: &ObstacleSchedule,
+        fps: usize,
+    ) {
+        let sleep = (1.0 / (fps as f64) * 1000.0).floor();
+        let mut trail = Vec::new();
+        let mut simulation = Simulation::new(path).with_schedule(schedule);
+        simulation.subscribe(|map, event| {
+            let (tick, arrived) = match *event {
+                Event::Step { tick, from, .. } => {
+                    trail.push(from);
+                    (tick, false)
+                }
+                Event::Arrived { tick, .. } => (tick, true),
+                Event::Blocked { .. } => return,
+            };
+            let obstacles = schedule.occupied_at(tick);
+            let frame = map.render_with(|node, glyph| {
+                if obstacles.contains(&node) {
+                    "☻"
+                } else if node == map.agent_pos && arrived {
+                    "✯"
+                } else if node != map.agent_pos && trail.contains(&node) {
+                    "☐"
+                } else {
+                    glyph
+                }
+                .to_string()
+            });
+            print!("\x1B[2J");
+            println!("{}\n{}", title, frame);
+            std::thread::sleep(Duration::from_millis(sleep as u64));
+        });
+        simulation.run(&mut self.clone());
+    }
+
+    pub fn find_path_bfs(&self, goal: (i32, i32)) -> Option<PathfindingResult> {
+        search::bfs(self, self.agent_pos, goal)
+    }
+
+    pub fn find_path_dijkstra(&self, goal: (i32, i32)) -> Option<PathfindingResult> {
+        search::dijkstra(self, self.agent_pos, goal)
+    }
+
+    pub fn find_path_gbfs(&self, goal: (i32, i32)) -> Option<PathfindingResult> {
+        search::gbfs(self, self.agent_pos, goal)
+    }
+
+    pub fn find_path_a_star(&self, goal: (i32, i32)) -> Option<PathfindingResult> {
+        search::a_star(self, self.agent_pos, goal)
+    }
+
+    pub fn find_path(&self, algorithm: Algorithm, goal: (i32, i32)) -> Option<PathfindingResult> {
+        match algorithm {
+            Algorithm::Bfs => self.find_path_bfs(goal),
+            Algorithm::Dijkstra => self.find_path_dijkstra(goal),
+            Algorithm::Gbfs => self.find_path_gbfs(goal),
+            Algorithm::AStar => self.find_path_a_star(goal),
+            Algorithm::IdaStar | Algorithm::Fringe => {
+                search::find_path(self, algorithm, self.agent_pos, goal)
+            }
+        }
+    }
+
+    fn cost_adjacent(&self, target: (i32, i32)) -> usize {
+        let cost = self
+            .grid
+            .get(target.0 as usize)
+            .and_then(|col| col.get(target.1 as usize));
+
+        if cost.is_none() {
+            usize::MAX
+        } else {
+            match cost {
+                Some(MapNode::Path(c)) => *c as usize,
+                _ => usize::MAX,
+            }
+        }
+    }
+
+    fn heuristic(&self, a: (i32, i32), b: (i32, i32)) -> usize {
+        ((a.0 - b.0).abs() + (a.1 - b.1).abs()) as usize
+    }
+}
+
+impl SearchSpace for Map {
+    type Node = (i32, i32);
+    type Neighbors<'a> = NeighborIterator<'a>;
+
+    fn neighbors(&self, node: (i32, i32)) -> NeighborIterator<'_> {
+        Map::neighbors(self, node)
+    }
+
+    fn cost(&self, _from: (i32, i32), to: (i32, i32)) -> usize {
+        self.cost_adjacent(to)
+    }
+
+    fn heuristic(&self, a: (i32, i32), b: (i32, i32)) -> usize {
+        Map::heuristic(self, a, b)
+    }
+
+    /// Every step costs the tile it enters, so the cheapest walkable tile.
+    fn cheapest_step(&self) -> usize {
+        self.grid
+            .iter()
+            .flatten()
+            .filter_map(|node| match node {
+                MapNode::Path(cost) => Some((*cost).max(0) as usize),
+                _ => None,
+            })
+            .min()
+            .unwrap_or(0)
+    }
+
+    fn cross_product(&self, start: (i32, i32), node: (i32, i32), goal: (i32, i32)) -> usize {
+        search::planar_cross_product(start, node, goal)
+    }
+
+    fn is_passable(&self, node: (i32, i32)) -> bool {
+        self.is_walkable(node)
+    }
+}
+
+impl FromStr for Map {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let grid = s
+            .lines()
+            .filter(|line| !line.trim().is_empty())
+            .enumerate()
+            .map(|(row, line)| {
+                line.split_whitespace()
+                    .map(|tile| match tile {
+                        "|" | "┃" => Ok(MapNode::VerticalObstacle),
+                        "-" | "━" => Ok(MapNode::HorizontalObstacle),
+                        tile => match tile.parse() {
+                            Ok(cost) if cost >= 0 => Ok(MapNode::Path(cost)),
+                            Ok(_) => Err(format!("Negative tile cost on row {}: {}", row, tile)),
+                            Err(_) => Err(format!("Invalid tile on row {}: {}", row, tile)),
+                        },
+                    })
+                    .collect::<Result<Vec<_>, _>>()
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let width = grid.first().map_or(0, |row| row.len());
+        if width == 0 {
+            return Err("Map is empty".to_string());
+        }
+        if let Some(row) = grid.iter().position(|row| row.len() != width) {
+            return Err(format!("Row {} does not have {} tiles", row, width));
+        }
+
+        Ok(Map {
+            width,
+            height: grid.len(),
+            grid,
+            agent_pos: (0, 0),
+        })
+    }
+}
+
+/// Runs every selected algorithm with every selected tie-breaking policy.
+fn run_searches<S: SearchSpace>(
+    options: &Options,
+    space: &S,
+    (start, goal): (S::Node, S::Node),
+) -> Vec<cli::Run<S::Node>> {
+    options
+        .algorithms
+        .iter()
+        .flat_map(|&algorithm| {
+            // Breadth first search and the iterative deepening ones have no priorities to break
+            // ties between.
+            let tie_breaks = match algorithm {
+                Algorithm::Bfs | Algorithm::IdaStar | Algorithm::Fringe => &options.tie_breaks[..1],
+                _ => &options.tie_breaks[..],
+            };
+            tie_breaks.iter().map(move |&tie_break| {
+                let res = search::find_path_using(
+                    space,
+                    options.frontier,
+                    algorithm,
+                    tie_break,
+                    start,
+                    goal,
+                );
+                (algorithm, tie_break, res)
+            })
+        })
+        .collect()
+}
+
+fn report<S: SearchSpace>(
+    options: &Options,
+    space: &S,
+    size: (usize, usize),
+    (start, goal): (S::Node, S::Node),
+    obstacles: Option<usize>,
+    overview: String,
+    show: impl Fn(Algorithm, &PathfindingResult<S::Node>),
+) where
+    S::Node: Serialize,
+{
+    let res = run_searches(options, space, (start, goal));
+
+    if options.format == Format::Json {
+        println!("{}", cli::format_json(options, size, (start, goal), &res));
+        return;
+    }
+
+    if let Some(obstacles) = obstacles {
+        if let Some(seed) = options.seed {
+            println!("Seed: {}", seed);
+        }
+        println!("Obstacles: {}", obstacles);
+    }
+    println!("{}", overview);
+
+    if !options.headless {
+        for (algorithm, _, path_res) in &res {
+            if let Some(path_res) = path_res {
+                show(*algorithm, path_res);
+            }
+        }
+    }
+
+    println!("{}", cli::format_table(&res));
+}
+
+/// Searches the graph read from `--map` and prints the paths by node name.
+fn run_graph(options: &Options) {
+    let (graph, (start, goal)) = match options.read_graph() {
+        Ok(graph) => graph,
+        Err(e) => {
+            eprintln!("{}", e);
+            std::process::exit(1);
+        }
+    };
+    if options.contract {
+        run_hierarchy(options, &graph, (start, goal));
+        return;
+    }
+    let res = run_searches(options, &graph, (start, goal))
+        .into_iter()
+        .map(|(algorithm, tie_break, res)| (algorithm, tie_break, res.map(|r| graph.named(r))))
+        .collect::<Vec<_>>();
+
+    if options.format == Format::Json {
+        println!(
+            "{}",
+            serde_json::json!({
+                "nodes": graph.len(),
+                "edges": graph.edge_count(),
+                "start": graph.name(start),
+                "goal": graph.name(goal),
+                "results": res
+                    .iter()
+                    .map(|(algorithm, tie_break, result)| serde_json::json!({
+                        "algorithm": algorithm,
+                        "tie_break": tie_break,
+                        "result": result,
+                    }))
+                    .collect::<Vec<_>>(),
+            })
+        );
+        return;
+    }
+
+    println!("Nodes: {}, Edges: {}", graph.len(), graph.edge_count());
+    if !options.headless {
+        for (algorithm, _, path_res) in &res {
+            if let Some(path_res) = path_res {
+                println!("{}: {}", algorithm.title(), path_res.path.join(" -> "));
+            }
+        }
+    }
+    println!("{}", cli::format_table(&res));
+}
+
+/// Preprocesses a contraction hierarchy of `graph` and answers the query with it.
+fn run_hierarchy(options: &Options, graph: &Graph, (start, goal): (usize, usize)) {
+    let hierarchy = ContractionHierarchy::new(graph);
+    let started = Instant::now();
+    let res = hierarchy.query(start, goal).map(|res| graph.named(res));
+    let query_time = started.elapsed();
+
+    if options.format == Format::Json {
+        println!(
+            "{}",
+            serde_json::json!({
+                "nodes": graph.len(),
+                "edges": graph.edge_count(),
+                "shortcuts": hierarchy.shortcuts(),
+                "preprocessing_ms": hierarchy.preprocessing_time().as_secs_f64() * 1000.0,
+                "query_ms": query_time.as_secs_f64() * 1000.0,
+                "start": graph.name(start),
+                "goal": graph.name(goal),
+                "result": res,
+            })
+        );
+        return;
+    }
+
+    println!(
+        "Contraction hierarchy - Nodes: {}, Edges: {}, Shortcuts: {}, Preprocessing: {:?}",
+        graph.len(),
+        graph.edge_count(),
+        hierarchy.shortcuts(),
+        hierarchy.preprocessing_time()
+    );
+    match res {
+        Some(res) => {
+            if !options.headless {
+                println!("{}", res.path.join(" -> "));
+            }
+            println!(
+                "Contraction hierarchy - Cost: {}, Length: {}, Nodes: {}, Expanded: {}, Query: {:?}",
+                res.cost_of_path,
+                res.path.len(),
+                res.nodes_visited,
+                res.nodes_expanded,
+                query_time
+            );
+        }
+        None => println!("Contraction hierarchy - Unreachable goal"),
+    }
+}
+
+fn run_batch(options: &Options, map: &Map) {
+    let queries = match options.read_queries(map) {
+        Ok(queries) => queries,
+        Err(e) => {
+            eprintln!("{}", e);
+            std::process::exit(1);
+        }
+    };
+
+    let mut batches = Vec::new();
+    for &algorithm in &options.algorithms {
+        let started = Instant::now();
+        let batch = map.find_paths_batch(&queries, algorithm, options.threads);
+        if options.format == Format::Text {
+            println!("{} - {:?}", algorithm.title(), started.elapsed());
+        }
+        batches.push((algorithm, batch));
+    }
+
+    match options.format {
+        Format::Text => println!("{}", cli::format_batch_table(&batches)),
+        Format::Json => println!("{}", cli::format_batch_json(&queries, &batches)),
+    }
+}
+
+fn space_time_demo(map: &Map, goal: (i32, i32), fps: usize) {
+    let mut schedule = ObstacleSchedule::new();
+    let patrol_row = map.height as i32 / 4;
+    let patrol_col = map.width as i32 / 3;
+    schedule.add_patrol(Patrol::back_and_forth(
+        &(0..map.width as i32)
+            .map(|col| (patrol_row, col))
+            .collect::<Vec<_>>(),
+    ));
+    schedule.add_patrol(Patrol::back_and_forth(
+        &(0..map.height as i32)
+            .map(|row| (row, patrol_col))
+            .collect::<Vec<_>>(),
+    ));
+    // A door next to the goal stays shut for the first few steps.
+    for t in 0..10 {
+        schedule.occupy((goal.0, goal.1 - 1), t);
+    }
+
+    match map.find_path_space_time(goal, &schedule, 1, 10 * map.width * map.height) {
+        Some(timed_res) => {
+            map.render_path("Space-time A*", &timed_res.positions(), &schedule, fps);
+            println!(
+                "Space-time A* - Overall path cost: {}, Nodes covered: {}, Time steps: {}",
+                timed_res.cost_of_path,
+                timed_res.nodes_visited,
+                timed_res.path.len() - 1
+            );
+        }
+        None => println!("Space-time A* - Unreachable goal"),
+    }
+}
+
+fn main() {
+    let options = match Options::parse(std::env::args().skip(1)) {
+        Ok(options) => options,
+        Err(e) => {
+            eprintln!("{}\n\n{}", e, cli::USAGE);
+            std::process::exit(2);
+        }
+    };
+    if options.help {
+        println!("{}", cli::USAGE);
+        return;
+    }
+    if options.benchmark {
+        println!("{}", bench::run(options.seed.unwrap_or(0)));
+        return;
+    }
+
+    if options.topology == Topology::Graph {
+        run_graph(&options);
+        return;
+    }
+
+    let (mut floors, obstacles) = match options.build_floors() {
+        Ok(floors) => floors,
+        Err(e) => {
+            eprintln!("{}", e);
+            std::process::exit(1);
+        }
+    };
+    if let Err(e) = options
+        .save_floors(&floors)
+        .and_then(|_| options.export_graph(&floors[0]))
+    {
+        eprintln!("{}", e);
+        std::process::exit(1);
+    }
+    let size = (floors[0].width, floors[0].height);
+    let goal = options.goal;
+
+    match options.topology {
+        Topology::Grid => {
+            let map = floors.remove(0);
+            if options.interactive {
+                let stdin = std::io::stdin();
+                let mut editor = Editor::new(map, goal);
+                if let Err(e) = editor.run(stdin.lock(), std::io::stdout()) {
+                    eprintln!("Editor stopped: {}", e);
+                }
+                return;
+            }
+
+            if options.queries_file.is_some() {
+                run_batch(&options, &map);
+                return;
+            }
+            if options.heatmap {
+                let mut heatmap =
+                    Heatmap::new(&map, options.color.unwrap_or_else(ColorMode::detect));
+                if let Some(algorithm) = options.overlay {
+                    heatmap = heatmap.with_expansions(algorithm, goal);
+                }
+                heatmap.print();
+                return;
+            }
+            if let Some(budget) = options.reach {
+                let reachable = map.reachable(budget);
+                let path = reachable.path_to(goal);
+                match options.format {
+                    Format::Text => {
+                        map.print_highlighted(|node| reachable.contains(node));
+                        println!("Reachable cells within {}: {}", budget, reachable.len());
+                        match path {
+                            Some(path) => println!(
+                                "Goal - Cost: {}, Length: {}",
+                                path.cost_of_path,
+                                path.path.len()
+                            ),
+                            None => println!("Goal - Out of reach"),
+                        }
+                    }
+                    Format::Json => {
+                        let mut cells = reachable.cells().collect::<Vec<_>>();
+                        cells.sort_unstable();
+                        let cells = cells
+                            .into_iter()
+                            .map(|(cell, cost)| {
+                                serde_json::json!({
+                                    "cell": cell,
+                                    "cost": cost,
+                                    "predecessor": reachable.predecessor(cell),
+                                })
+                            })
+                            .collect::<Vec<_>>();
+                        println!(
+                            "{}",
+                            serde_json::json!({
+                                "budget": reachable.budget(),
+                                "cells": cells,
+                                "goal_cost": reachable.cost(goal),
+                                "goal": path,
+                            })
+                        );
+                    }
+                }
+                return;
+            }
+            if let Some(k) = options.alternatives {
+                let paths = map.k_shortest_paths(goal, k, options.max_overlap);
+                match options.format {
+                    Format::Text => {
+                        if paths.is_empty() {
+                            println!("Unreachable goal");
+                        }
+                        for (i, path) in paths.iter().enumerate() {
+                            if !options.headless {
+                                let mut drawn = map.clone();
+                                for &node in &path.path[1..] {
+                                    drawn.draw(node, "☐");
+                                }
+                                drawn.print();
+                            }
+                            println!(
+                                "Path {} - Cost: {}, Length: {}",
+                                i + 1,
+                                path.cost_of_path,
+                                path.path.len()
+                            );
+                        }
+                    }
+                    Format::Json => println!("{}", serde_json::json!(paths)),
+                }
+                return;
+            }
+            if let Some((turn, reverse)) = options.turns {
+                let vehicle = Vehicle {
+                    turn,
+                    reverse,
+                    diagonal: options.diagonal,
+                };
+                let res = map.find_path_turning(goal, options.heading, &vehicle);
+                match (options.format, res) {
+                    (Format::Text, Some(res)) => {
+                        if !options.headless {
+                            res.draw_on(&map).print();
+                        }
+                        println!(
+                            "Turning A* - Cost: {}, Length: {}, Turns: {}, Reversals: {}, Nodes: {}, Expanded: {}",
+                            res.result.cost_of_path,
+                            res.result.path.len(),
+                            res.turns,
+                            res.reversals,
+                            res.result.nodes_visited,
+                            res.result.nodes_expanded
+                        );
+                    }
+                    (Format::Text, None) => println!("Turning A* - Unreachable goal"),
+                    (Format::Json, res) => println!("{}", serde_json::json!(res)),
+                }
+                return;
+            }
+            if options.contract {
+                let graph = Graph::from(&map);
+                let node = |(row, col): (i32, i32)| graph.node(&format!("{},{}", row, col));
+                match (node(map.agent_pos), node(goal)) {
+                    (Some(start), Some(goal)) => run_hierarchy(&options, &graph, (start, goal)),
+                    _ => {
+                        eprintln!("The start and goal have to be walkable");
+                        std::process::exit(1);
+                    }
+                }
+                return;
+            }
+            if let Some(radius) = options.fog {
+                let exploration = map.explore(goal, radius);
+                match options.format {
+                    Format::Text => {
+                        println!(
+                            "Fog of war (radius {}) - Reached: {}, Distance: {}, Cost: {}, Replans: {}",
+                            radius,
+                            if exploration.reached { "yes" } else { "no" },
+                            exploration.distance,
+                            exploration.cost,
+                            exploration.replans
+                        );
+                        exploration.known_map().print();
+                    }
+                    Format::Json => println!("{}", serde_json::json!(exploration)),
+                }
+                return;
+            }
+
+            let show = |algorithm: Algorithm, path_res: &PathfindingResult| {
+                map.render_path(
+                    algorithm.title(),
+                    &path_res.path,
+                    &ObstacleSchedule::new(),
+                    options.fps,
+                )
+            };
+            match options.clearance {
+                Some(min) => {
+                    let space = Clearance::new(&map, min);
+                    let overview = format!(
+                        "{}\nClearance - Start: {}, Goal: {}, Required: {}",
+                        map.render_highlighted(
+                            |node| map.is_walkable(node) && !space.is_clear(node)
+                        ),
+                        space.clearance(map.agent_pos),
+                        space.clearance(goal),
+                        min
+                    );
+                    report(
+                        &options,
+                        &space,
+                        size,
+                        (map.agent_pos, goal),
+                        obstacles,
+                        overview,
+                        show,
+                    );
+                }
+                None => report(
+                    &options,
+                    &map,
+                    size,
+                    (map.agent_pos, goal),
+                    obstacles,
+                    map.render(),
+                    show,
+                ),
+            }
+            if !options.headless {
+                space_time_demo(&map, goal, options.fps);
+            }
+        }
+        Topology::Hex => {
+            let hex = HexMap::new(floors.remove(0));
+            report(
+                &options,
+                &hex,
+                size,
+                (hex.agent_pos(), Axial::from_offset(goal)),
+                obstacles,
+                hex.render(),
+                |algorithm, path_res| {
+                    let mut view = hex.clone();
+                    for p in path_res.path.iter().skip(1) {
+                        view.draw(*p, "☐");
+                    }
+                    println!("{}\n{}", algorithm.title(), view.render());
+                },
+            );
+        }
+        Topology::Graph => unreachable!("Graphs are searched by run_graph"),
+        Topology::Voxel => {
+            let mut voxels = VoxelMap::new(floors).with_diagonal_moves(options.diagonal);
+            voxels.set_agent_pos(Voxel::new(
+                options.start_floor,
+                options.start.0,
+                options.start.1,
+            ));
+            report(
+                &options,
+                &voxels,
+                size,
+                (
+                    voxels.agent_pos(),
+                    Voxel::new(options.goal_floor, goal.0, goal.1),
+                ),
+                obstacles,
+                voxels.render(),
+                |algorithm, path_res| {
+                    let mut view = voxels.clone();
+                    for p in path_res.path.iter().skip(1) {
+                        view.draw(*p, "☐");
+                    }
+                    println!("{}\n{}", algorithm.title(), view.render());
+                },
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{Map, MapNode};
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+
+    #[test]
+    fn test_generate_map_stops_when_the_map_is_full() {
+        let mut map = Map::new(10, 10);
+        let placed = map.generate_map(&mut StdRng::seed_from_u64(3), (200, 201));
+
+        assert!(placed < 200);
+        assert!(map
+            .grid
+            .iter()
+            .flatten()
+            .all(|node| !matches!(node, MapNode::Path(_))));
+    }
+
+    #[test]
+    fn test_parse_map() {
+        let map = "1 2 |\n0 - 3".parse::<Map>().unwrap();
+        assert_eq!((map.width, map.height), (3, 2));
+        assert!(matches!(map.grid[1][0], MapNode::Path(0)));
+
+        assert!("1 2\n3".parse::<Map>().is_err());
+        assert!("1 x".parse::<Map>().is_err());
+        assert!("1 -2".parse::<Map>().is_err());
+    }
+}
@@ -0,0 +1,325 @@
+use crate::search::SearchSpace;
+use crate::{Map, NeighborIterator};
+
+/// Chebyshev distance from `node` to the nearest cell of the square at `corner` with side `size`.
+fn distance_to_square(node: (i32, i32), corner: (i32, i32), size: i32) -> usize {
+    let axis = |at: i32, from: i32| (from - at).max(at - (from + size - 1)).max(0) as usize;
+    axis(node.0, corner.0).max(axis(node.1, corner.1))
+}
+
+#[derive(Debug, Clone)]
+enum Quad {
+    Empty,
+    Full,
+    Split(Box<[Quad; 4]>),
+}
+
+/// A region quadtree over the cells of a map that aren't walkable. Squares that are entirely
+/// free or entirely blocked are stored as single leaves, so queries skip over open areas and
+/// solid walls alike. Distances are Chebyshev distances, the ones that matter for square
+/// agents.
+#[derive(Debug, Clone)]
+pub struct QuadTree {
+    root: Quad,
+    size: i32,
+    width: usize,
+    height: usize,
+}
+
+impl QuadTree {
+    pub fn new(map: &Map) -> Self {
+        let size = map.width.max(map.height).next_power_of_two() as i32;
+        QuadTree {
+            root: QuadTree::build(map, (0, 0), size),
+            size,
+            width: map.width,
+            height: map.height,
+        }
+    }
+
+    fn build(map: &Map, corner: (i32, i32), size: i32) -> Quad {
+        if corner.0 >= map.height as i32 || corner.1 >= map.width as i32 {
+            return Quad::Empty;
+        }
+        if size == 1 {
+            return if map.is_walkable(corner) {
+                Quad::Empty
+            } else {
+                Quad::Full
+            };
+        }
+
+        let half = size / 2;
+        let children = [(0, 0), (0, half), (half, 0), (half, half)]
+            .map(|(dr, dc)| QuadTree::build(map, (corner.0 + dr, corner.1 + dc), half));
+        match children {
+            [Quad::Empty, Quad::Empty, Quad::Empty, Quad::Empty] => Quad::Empty,
+            [Quad::Full, Quad::Full, Quad::Full, Quad::Full] => Quad::Full,
+            children => Quad::Split(Box::new(children)),
+        }
+    }
+
+    /// Lowers `within` to the distance from `node` to the closest blocked leaf below `quad`, if
+    /// that is any closer. Children are visited closest first so that the rest can be pruned.
+    fn nearest(quad: &Quad, corner: (i32, i32), size: i32, node: (i32, i32), within: &mut usize) {
+        let distance = distance_to_square(node, corner, size);
+        if distance >= *within {
+            return;
+        }
+        match quad {
+            Quad::Empty => {}
+            Quad::Full => *within = distance,
+            Quad::Split(children) => {
+                let half = size / 2;
+                let mut children = [(0, 0), (0, half), (half, 0), (half, half)]
+                    .iter()
+                    .zip(children.iter())
+                    .map(|(&(dr, dc), child)| ((corner.0 + dr, corner.1 + dc), child))
+                    .collect::<Vec<_>>();
+                children.sort_by_key(|&(corner, _)| distance_to_square(node, corner, half));
+                for (corner, child) in children {
+                    QuadTree::nearest(child, corner, half, node, within);
+                }
+            }
+        }
+    }
+
+    /// Chebyshev distance from `node` to the closest obstacle, 0 on an obstacle and `None` if
+    /// the map has none.
+    pub fn nearest_obstacle(&self, node: (i32, i32)) -> Option<usize> {
+        let mut within = usize::MAX;
+        QuadTree::nearest(&self.root, (0, 0), self.size, node, &mut within);
+        Some(within).filter(|&distance| distance != usize::MAX)
+    }
+
+    /// Whether any cell of the rectangle between the corners `from` and `to`, both included, is
+    /// blocked. Cells outside the map don't count.
+    pub fn is_blocked(&self, from: (i32, i32), to: (i32, i32)) -> bool {
+        let (top, bottom) = (from.0.min(to.0), from.0.max(to.0));
+        let (left, right) = (from.1.min(to.1), from.1.max(to.1));
+        let mut stack = vec![(&self.root, (0, 0), self.size)];
+        while let Some((quad, corner, size)) = stack.pop() {
+            let overlaps = corner.0 <= bottom
+                && top < corner.0 + size
+                && corner.1 <= right
+                && left < corner.1 + size;
+            match quad {
+                _ if !overlaps => {}
+                Quad::Empty => {}
+                Quad::Full => return true,
+                Quad::Split(children) => {
+                    let half = size / 2;
+                    for (&(dr, dc), child) in [(0, 0), (0, half), (half, 0), (half, half)]
+                        .iter()
+                        .zip(children.iter())
+                    {
+                        stack.push((child, (corner.0 + dr, corner.1 + dc), half));
+                    }
+                }
+            }
+        }
+        false
+    }
+
+    /// How far `node` is from the nearest obstacle or the edge of the map: an agent covering
+    /// every cell within Chebyshev distance `r` of its center fits wherever the clearance is
+    /// above `r`. Walkable cells have a clearance of at least 1, obstacles 0.
+    pub fn clearance(&self, node: (i32, i32)) -> usize {
+        let edge = [
+            node.0 + 1,
+            node.1 + 1,
+            self.height as i32 - node.0,
+            self.width as i32 - node.1,
+        ]
+        .iter()
+        .copied()
+        .min()
+        .unwrap_or(0)
+        .max(0) as usize;
+        self.nearest_obstacle(node)
+            .map_or(edge, |distance| distance.min(edge))
+    }
+}
+
+/// A map restricted to the cells with a clearance of at least `min`, that is the centers an
+/// agent covering a square of side `2 * min - 1` can take.
+pub struct Clearance<'a> {
+    map: &'a Map,
+    tree: QuadTree,
+    min: usize,
+}
+
+impl<'a> Clearance<'a> {
+    pub fn new(map: &'a Map, min: usize) -> Self {
+        Clearance {
+            map,
+            tree: QuadTree::new(map),
+            min,
+        }
+    }
+
+    /// Whether the agent's square around `node` lies on the map and covers no obstacle.
+    pub fn is_clear(&self, node: (i32, i32)) -> bool {
+        let reach = self.min.saturating_sub(1) as i32;
+        let from = (node.0 - reach, node.1 - reach);
+        let to = (node.0 + reach, node.1 + reach);
+        self.map.contains(from) && self.map.contains(to) && !self.tree.is_blocked(from, to)
+    }
+
+    pub fn clearance(&self, node: (i32, i32)) -> usize {
+        self.tree.clearance(node)
+    }
+}
+
+pub struct ClearNeighbors<'a> {
+    inner: NeighborIterator<'a>,
+    space: &'a Clearance<'a>,
+}
+
+impl<'a> Iterator for ClearNeighbors<'a> {
+    type Item = (i32, i32);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let space = self.space;
+        self.inner.find(|&node| space.is_clear(node))
+    }
+}
+
+impl<'a> SearchSpace for Clearance<'a> {
+    type Node = (i32, i32);
+    type Neighbors<'b>
+        = ClearNeighbors<'b>
+    where
+        Self: 'b;
+
+    fn neighbors(&self, node: (i32, i32)) -> ClearNeighbors<'_> {
+        ClearNeighbors {
+            inner: self.map.neighbors(node),
+            space: self,
+        }
+    }
+
+    fn cost(&self, from: (i32, i32), to: (i32, i32)) -> usize {
+        self.map.cost(from, to)
+    }
+
+    fn heuristic(&self, a: (i32, i32), b: (i32, i32)) -> usize {
+        SearchSpace::heuristic(self.map, a, b)
+    }
+
+    fn cheapest_step(&self) -> usize {
+        self.map.cheapest_step()
+    }
+
+    fn cross_product(&self, start: (i32, i32), node: (i32, i32), goal: (i32, i32)) -> usize {
+        self.map.cross_product(start, node, goal)
+    }
+
+    fn is_passable(&self, node: (i32, i32)) -> bool {
+        self.is_clear(node)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::quadtree::{Clearance, QuadTree};
+    use crate::search;
+    use crate::{Map, MapNode};
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+
+    fn open_map(width: usize, height: usize) -> Map {
+        let mut map = Map::new(width, height);
+        for node in map.grid.iter_mut().flatten() {
+            *node = MapNode::Path(1);
+        }
+        map
+    }
+
+    #[test]
+    fn test_queries_match_brute_force() {
+        let mut map = Map::new(13, 9);
+        map.generate_map(&mut StdRng::seed_from_u64(6), (5, 10));
+        let tree = QuadTree::new(&map);
+        let cells = (0..9).flat_map(|row| (0..13).map(move |col| (row, col)));
+        let obstacles = cells
+            .clone()
+            .filter(|&node| !map.is_walkable(node))
+            .collect::<Vec<_>>();
+
+        for node in cells.clone() {
+            let nearest = obstacles
+                .iter()
+                .map(|o| (o.0 - node.0).abs().max((o.1 - node.1).abs()) as usize)
+                .min();
+            assert_eq!(tree.nearest_obstacle(node), nearest, "{:?}", node);
+        }
+        for from in cells.clone().step_by(7) {
+            for to in cells.clone().step_by(5) {
+                let blocked = obstacles.iter().any(|o| {
+                    (from.0.min(to.0)..=from.0.max(to.0)).contains(&o.0)
+                        && (from.1.min(to.1)..=from.1.max(to.1)).contains(&o.1)
+                });
+                assert_eq!(tree.is_blocked(from, to), blocked, "{:?} {:?}", from, to);
+            }
+        }
+    }
+
+    #[test]
+    fn test_clearance() {
+        let mut map = open_map(7, 7);
+        let tree = QuadTree::new(&map);
+        assert_eq!(tree.nearest_obstacle((3, 3)), None);
+        assert_eq!(tree.clearance((3, 3)), 4);
+        assert_eq!(tree.clearance((0, 3)), 1);
+
+        map.grid[2][2] = MapNode::VerticalObstacle;
+        let tree = QuadTree::new(&map);
+        assert_eq!(tree.clearance((2, 2)), 0);
+        assert_eq!(tree.clearance((3, 3)), 1);
+        assert_eq!(tree.clearance((4, 4)), 2);
+        assert!(tree.is_blocked((0, 0), (2, 2)));
+        assert!(!tree.is_blocked((3, 0), (6, 6)));
+        assert!(!tree.is_blocked((7, 7), (20, 20)));
+
+        for min in 0..5 {
+            let space = Clearance::new(&map, min);
+            for node in (0..7).flat_map(|row| (0..7).map(move |col| (row, col))) {
+                assert_eq!(
+                    space.is_clear(node),
+                    tree.clearance(node) >= min.max(1),
+                    "{:?} {}",
+                    node,
+                    min
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_wide_agents_avoid_narrow_gaps() {
+        // A wall across the middle with a one cell gap on the left and a wide one on the right.
+        let mut map = open_map(14, 11);
+        for col in 0..14 {
+            if col != 1 && !(8..=12).contains(&col) {
+                map.grid[5][col] = MapNode::HorizontalObstacle;
+            }
+        }
+        map.agent_pos = (2, 2);
+
+        let find = |min| search::a_star(&Clearance::new(&map, min), (2, 2), (8, 2));
+
+        let thin = find(1).unwrap();
+        let plain = search::a_star(&map, (2, 2), (8, 2)).unwrap();
+        assert_eq!(thin.cost_of_path, plain.cost_of_path);
+        assert!(thin.path.contains(&(5, 1)));
+
+        let wide = find(2).unwrap();
+        let tree = QuadTree::new(&map);
+        assert!(wide.path.iter().all(|&node| tree.clearance(node) >= 2));
+        assert!(wide.path.iter().any(|&(row, col)| row == 5 && col >= 9));
+        assert!(wide.cost_of_path > thin.cost_of_path);
+
+        assert!(find(4).is_none());
+    }
+}
@@ -0,0 +1,156 @@
+use crate::search::{PriorityState, SearchSpace};
+use crate::{Map, PathfindingResult};
+use std::collections::{BinaryHeap, HashMap};
+
+/// Every node reachable from `origin` for at most `budget`, with the cheapest cost of getting
+/// there and the node it is reached from.
+pub struct Reachable<N = (i32, i32)> {
+    origin: N,
+    budget: usize,
+    cells: HashMap<N, (usize, N)>,
+    expanded: usize,
+}
+
+impl<N: Copy + Eq + std::hash::Hash> Reachable<N> {
+    pub fn budget(&self) -> usize {
+        self.budget
+    }
+
+    pub fn len(&self) -> usize {
+        self.cells.len()
+    }
+
+    pub fn contains(&self, node: N) -> bool {
+        self.cells.contains_key(&node)
+    }
+
+    pub fn cost(&self, node: N) -> Option<usize> {
+        self.cells.get(&node).map(|&(cost, _)| cost)
+    }
+
+    pub fn predecessor(&self, node: N) -> Option<N> {
+        self.cells.get(&node).map(|&(_, predecessor)| predecessor)
+    }
+
+    /// Reachable nodes with their costs, in no particular order.
+    pub fn cells(&self) -> impl Iterator<Item = (N, usize)> + '_ {
+        self.cells.iter().map(|(&node, &(cost, _))| (node, cost))
+    }
+
+    /// Follows the predecessors back from `node`, no search needed.
+    pub fn path_to(&self, node: N) -> Option<PathfindingResult<N>> {
+        let (cost, _) = *self.cells.get(&node)?;
+        let mut path = vec![node];
+        let mut current = node;
+        while current != self.origin {
+            current = self.predecessor(current)?;
+            path.push(current);
+        }
+        path.reverse();
+
+        Some(PathfindingResult {
+            nodes_visited: self.cells.len(),
+            nodes_expanded: self.expanded,
+            cost_of_path: cost,
+            path,
+        })
+    }
+}
+
+/// Dijkstra from `origin` that stops at `budget`, settling every node within it.
+pub fn reachable<S: SearchSpace>(space: &S, origin: S::Node, budget: usize) -> Reachable<S::Node> {
+    let mut cells = HashMap::new();
+    let mut frontier = BinaryHeap::new();
+    let mut expanded = 0;
+    cells.insert(origin, (0, origin));
+    frontier.push(PriorityState {
+        cost: 0,
+        tie: 0,
+        pos: origin,
+    });
+
+    while let Some(current) = frontier.pop() {
+        if current.cost > cells[&current.pos].0 {
+            continue;
+        }
+        expanded += 1;
+        for neighbor in space.neighbors(current.pos) {
+            let cost = current
+                .cost
+                .saturating_add(space.cost(current.pos, neighbor));
+            if cost <= budget && cells.get(&neighbor).is_none_or(|&(known, _)| cost < known) {
+                cells.insert(neighbor, (cost, current.pos));
+                frontier.push(PriorityState {
+                    cost,
+                    tie: 0,
+                    pos: neighbor,
+                });
+            }
+        }
+    }
+
+    Reachable {
+        origin,
+        budget,
+        cells,
+        expanded,
+    }
+}
+
+impl Map {
+    /// All cells the agent can reach for at most `budget`.
+    pub fn reachable(&self, budget: usize) -> Reachable {
+        reachable(self, self.agent_pos, budget)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::search;
+    use crate::{Map, MapNode};
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+
+    #[test]
+    fn test_reachable_matches_dijkstra() {
+        let mut map = Map::new(15, 15);
+        map.generate_map(&mut StdRng::seed_from_u64(8), (10, 20));
+        map.grid[0][0] = MapNode::Path(1);
+        let reachable = map.reachable(30);
+
+        for row in 0..15 {
+            for col in 0..15 {
+                let best = search::dijkstra(&map, (0, 0), (row, col)).map(|r| r.cost_of_path);
+                match best {
+                    Some(cost) if cost <= 30 => {
+                        assert_eq!(reachable.cost((row, col)), Some(cost));
+                        let path = reachable.path_to((row, col)).unwrap();
+                        assert_eq!(path.cost_of_path, cost);
+                        assert_eq!(path.validate(&map, (0, 0), (row, col)), Ok(()));
+                    }
+                    _ => assert!(!reachable.contains((row, col))),
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_budget_zero_and_highlight() {
+        let mut map = Map::new(4, 1);
+        for node in map.grid.iter_mut().flatten() {
+            *node = MapNode::Path(2);
+        }
+        map.grid[0][1] = MapNode::Path(0);
+
+        assert_eq!(map.reachable(0).len(), 2);
+        let reachable = map.reachable(2);
+        assert_eq!(reachable.len(), 3);
+        assert_eq!(reachable.predecessor((0, 2)), Some((0, 1)));
+        assert_eq!(
+            map.render_highlighted(|node| reachable.contains(node))
+                .matches("\x1B[7m")
+                .count(),
+            3
+        );
+    }
+}
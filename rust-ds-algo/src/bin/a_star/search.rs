@@ -0,0 +1,588 @@
+use crate::frontier::{BucketQueue, Frontier, FrontierKind, RadixHeap};
+use crate::ida;
+use crate::{Algorithm, PathfindingResult};
+use serde::Serialize;
+use std::cmp::Ordering;
+use std::collections::hash_map::Entry;
+use std::collections::{BinaryHeap, HashMap, VecDeque};
+use std::fmt::Debug;
+use std::hash::Hash;
+use std::str::FromStr;
+
+/// Anything the searches can walk: a grid, a hex map, a voxel building or a plain graph.
+pub trait SearchSpace {
+    type Node: Copy + Eq + Hash + Ord + Debug;
+    type Neighbors<'a>: Iterator<Item = Self::Node>
+    where
+        Self: 'a;
+
+    /// Nodes reachable from `node` in a single step.
+    fn neighbors(&self, node: Self::Node) -> Self::Neighbors<'_>;
+
+    /// Cost of stepping from `from` to its neighbor `to`.
+    fn cost(&self, from: Self::Node, to: Self::Node) -> usize;
+
+    /// Estimated number of steps between two nodes, used by the informed searches.
+    fn heuristic(&self, a: Self::Node, b: Self::Node) -> usize;
+
+    /// A lower bound on the cost of a single step. The optimal searches scale `heuristic` by it
+    /// so that they never overestimate, which matters once tiles can be free.
+    fn cheapest_step(&self) -> usize {
+        1
+    }
+
+    /// How far `node` strays from the straight line between `start` and `goal`, used by
+    /// `TieBreak::CrossProduct`. Spaces without a geometry keep every node on the line.
+    fn cross_product(&self, _start: Self::Node, _node: Self::Node, _goal: Self::Node) -> usize {
+        0
+    }
+
+    /// Whether a path may pass through `node`, used to tell steps into obstacles apart when
+    /// validating paths. Spaces without obstacles keep every node passable.
+    fn is_passable(&self, _node: Self::Node) -> bool {
+        true
+    }
+
+    /// Whether `to` is a single step away from `from`.
+    fn is_adjacent(&self, from: Self::Node, to: Self::Node) -> bool {
+        self.neighbors(from).any(|neighbor| neighbor == to)
+    }
+}
+
+/// Magnitude of the cross product of `node - goal` and `start - goal` on a plane.
+pub fn planar_cross_product(start: (i32, i32), node: (i32, i32), goal: (i32, i32)) -> usize {
+    let (dy1, dx1) = (node.0 - goal.0, node.1 - goal.1);
+    let (dy2, dx2) = (start.0 - goal.0, start.1 - goal.1);
+    (dx1 as i64 * dy2 as i64 - dx2 as i64 * dy1 as i64).unsigned_abs() as usize
+}
+
+/// How the best-first searches order states of equal cost. Every frontier kind honours it.
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum TieBreak {
+    /// The larger position first, which sweeps plateaus in coordinate order.
+    Position,
+    /// The deeper state first, so that the search follows through on one of the tied paths.
+    HigherG,
+    /// The state estimated closest to the goal first.
+    LowerH,
+    Lifo,
+    Fifo,
+    /// The state closest to the straight line from start to goal first.
+    CrossProduct,
+}
+
+impl TieBreak {
+    pub const ALL: [TieBreak; 6] = [
+        TieBreak::Position,
+        TieBreak::HigherG,
+        TieBreak::LowerH,
+        TieBreak::Lifo,
+        TieBreak::Fifo,
+        TieBreak::CrossProduct,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            TieBreak::Position => "position",
+            TieBreak::HigherG => "higher-g",
+            TieBreak::LowerH => "lower-h",
+            TieBreak::Lifo => "lifo",
+            TieBreak::Fifo => "fifo",
+            TieBreak::CrossProduct => "cross-product",
+        }
+    }
+
+    /// Secondary key of a state reached at cost `g`, lower keys are popped first. `pushed` counts
+    /// the states pushed before this one.
+    fn key<S: SearchSpace>(
+        self,
+        space: &S,
+        (start, goal): (S::Node, S::Node),
+        node: S::Node,
+        g: usize,
+        pushed: usize,
+    ) -> usize {
+        match self {
+            TieBreak::Position => 0,
+            TieBreak::HigherG => usize::MAX - g,
+            TieBreak::LowerH => space.heuristic(goal, node),
+            TieBreak::Lifo => usize::MAX - pushed,
+            TieBreak::Fifo => pushed,
+            TieBreak::CrossProduct => space.cross_product(start, node, goal),
+        }
+    }
+}
+
+impl FromStr for TieBreak {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        TieBreak::ALL
+            .iter()
+            .find(|tie_break| tie_break.name() == s.to_lowercase())
+            .copied()
+            .ok_or(format!("Unknown tie-breaking policy: {}", s))
+    }
+}
+
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct PriorityState<N> {
+    pub cost: usize,
+    /// Orders states of equal cost, see `TieBreak`.
+    pub tie: usize,
+    pub pos: N,
+}
+
+impl<N: Ord> PartialOrd<Self> for PriorityState<N> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl<N: Ord> Ord for PriorityState<N> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        other
+            .cost
+            .cmp(&self.cost)
+            .then_with(|| other.tie.cmp(&self.tie))
+            .then_with(|| self.pos.cmp(&other.pos))
+    }
+}
+
+/// Frontier and bookkeeping maps of a search. Reusing one across searches keeps the allocations
+/// around, which adds up when running many queries. `F` is the priority queue used by the
+/// best-first searches.
+pub struct SearchScratch<N, F = BinaryHeap<PriorityState<N>>> {
+    queue: VecDeque<N>,
+    frontier: F,
+    history: HashMap<N, N>,
+    cost_until: HashMap<N, usize>,
+}
+
+impl<N: Copy + Eq + Hash + Ord, F: Frontier<N>> SearchScratch<N, F> {
+    pub fn new() -> Self {
+        SearchScratch {
+            queue: VecDeque::new(),
+            frontier: F::default(),
+            history: HashMap::new(),
+            cost_until: HashMap::new(),
+        }
+    }
+
+    fn clear(&mut self) {
+        self.queue.clear();
+        self.frontier.clear();
+        self.history.clear();
+        self.cost_until.clear();
+    }
+}
+
+impl<N: Copy + Eq + Hash + Ord, F: Frontier<N>> Default for SearchScratch<N, F> {
+    fn default() -> Self {
+        SearchScratch::new()
+    }
+}
+
+pub fn find_path<S: SearchSpace>(
+    space: &S,
+    algorithm: Algorithm,
+    start: S::Node,
+    goal: S::Node,
+) -> Option<PathfindingResult<S::Node>> {
+    find_path_with(
+        space,
+        &mut SearchScratch::<_>::new(),
+        algorithm,
+        TieBreak::Position,
+        start,
+        goal,
+    )
+}
+
+/// `find_path` with the best-first searches running on the given kind of priority queue.
+pub fn find_path_using<S: SearchSpace>(
+    space: &S,
+    frontier: FrontierKind,
+    algorithm: Algorithm,
+    tie_break: TieBreak,
+    start: S::Node,
+    goal: S::Node,
+) -> Option<PathfindingResult<S::Node>> {
+    match frontier {
+        FrontierKind::BinaryHeap => find_path_with(
+            space,
+            &mut SearchScratch::<_>::new(),
+            algorithm,
+            tie_break,
+            start,
+            goal,
+        ),
+        FrontierKind::Bucket => find_path_with(
+            space,
+            &mut SearchScratch::<_, BucketQueue<_>>::new(),
+            algorithm,
+            tie_break,
+            start,
+            goal,
+        ),
+        FrontierKind::Radix => find_path_with(
+            space,
+            &mut SearchScratch::<_, RadixHeap<_>>::new(),
+            algorithm,
+            tie_break,
+            start,
+            goal,
+        ),
+    }
+}
+
+pub fn find_path_with<S: SearchSpace, F: Frontier<S::Node>>(
+    space: &S,
+    scratch: &mut SearchScratch<S::Node, F>,
+    algorithm: Algorithm,
+    tie_break: TieBreak,
+    start: S::Node,
+    goal: S::Node,
+) -> Option<PathfindingResult<S::Node>> {
+    scratch.clear();
+    let res = match algorithm {
+        Algorithm::Bfs => bfs_with(space, scratch, start, goal),
+        Algorithm::Dijkstra => best_first(space, scratch, tie_break, start, goal, |_, _| 0),
+        Algorithm::Gbfs => gbfs_with(space, scratch, tie_break, start, goal),
+        Algorithm::AStar => {
+            let cheapest_step = space.cheapest_step();
+            best_first(space, scratch, tie_break, start, goal, |space, node| {
+                space.heuristic(goal, node) * cheapest_step
+            })
+        }
+        Algorithm::IdaStar => ida::ida_star(space, start, goal),
+        Algorithm::Fringe => ida::fringe(space, start, goal),
+    };
+
+    if cfg!(debug_assertions) {
+        if let Some(Err(violation)) = res.as_ref().map(|res| res.validate(space, start, goal)) {
+            panic!(
+                "{} returned an invalid path: {}",
+                algorithm.title(),
+                violation
+            );
+        }
+    }
+    res
+}
+
+pub fn bfs<S: SearchSpace>(
+    space: &S,
+    start: S::Node,
+    goal: S::Node,
+) -> Option<PathfindingResult<S::Node>> {
+    find_path(space, Algorithm::Bfs, start, goal)
+}
+
+pub fn dijkstra<S: SearchSpace>(
+    space: &S,
+    start: S::Node,
+    goal: S::Node,
+) -> Option<PathfindingResult<S::Node>> {
+    find_path(space, Algorithm::Dijkstra, start, goal)
+}
+
+pub fn gbfs<S: SearchSpace>(
+    space: &S,
+    start: S::Node,
+    goal: S::Node,
+) -> Option<PathfindingResult<S::Node>> {
+    find_path(space, Algorithm::Gbfs, start, goal)
+}
+
+pub fn a_star<S: SearchSpace>(
+    space: &S,
+    start: S::Node,
+    goal: S::Node,
+) -> Option<PathfindingResult<S::Node>> {
+    find_path(space, Algorithm::AStar, start, goal)
+}
+
+fn bfs_with<S: SearchSpace, F>(
+    space: &S,
+    scratch: &mut SearchScratch<S::Node, F>,
+    start: S::Node,
+    goal: S::Node,
+) -> Option<PathfindingResult<S::Node>> {
+    let frontier = &mut scratch.queue;
+    let history = &mut scratch.history;
+    let cost_until = &mut scratch.cost_until;
+    let mut expanded = 0;
+    frontier.push_front(start);
+    history.insert(start, start);
+    cost_until.insert(start, 0);
+
+    while let Some(current) = frontier.pop_back() {
+        if current == goal {
+            break;
+        }
+        expanded += 1;
+
+        for neighbor in space.neighbors(current) {
+            if let Entry::Vacant(entry) = history.entry(neighbor) {
+                let cost = cost_until[&current] + space.cost(current, neighbor);
+                cost_until.insert(neighbor, cost);
+                frontier.push_front(neighbor);
+                entry.insert(current);
+            }
+        }
+    }
+
+    construct_path(start, goal, history, cost_until, expanded)
+}
+
+fn gbfs_with<S: SearchSpace, F: Frontier<S::Node>>(
+    space: &S,
+    scratch: &mut SearchScratch<S::Node, F>,
+    tie_break: TieBreak,
+    start: S::Node,
+    goal: S::Node,
+) -> Option<PathfindingResult<S::Node>> {
+    let frontier = &mut scratch.frontier;
+    let history = &mut scratch.history;
+    let cost_until = &mut scratch.cost_until;
+    let (mut pushed, mut expanded) = (1, 0);
+    frontier.push(PriorityState {
+        cost: 0,
+        tie: 0,
+        pos: start,
+    });
+    history.insert(start, start);
+    cost_until.insert(start, 0);
+
+    while let Some(current) = frontier.pop() {
+        if current.pos == goal {
+            break;
+        }
+        expanded += 1;
+        for neighbor in space.neighbors(current.pos) {
+            if let Entry::Vacant(entry) = history.entry(neighbor) {
+                let cost = cost_until[&current.pos] + space.cost(current.pos, neighbor);
+                cost_until.insert(neighbor, cost);
+                frontier.push(PriorityState {
+                    cost: space.heuristic(goal, neighbor),
+                    tie: tie_break.key(space, (start, goal), neighbor, cost, pushed),
+                    pos: neighbor,
+                });
+                pushed += 1;
+                entry.insert(current.pos);
+            }
+        }
+    }
+
+    construct_path(start, goal, history, cost_until, expanded)
+}
+
+/// Dijkstra when `heuristic` is zero, A* otherwise.
+fn best_first<S: SearchSpace, F: Frontier<S::Node>>(
+    space: &S,
+    scratch: &mut SearchScratch<S::Node, F>,
+    tie_break: TieBreak,
+    start: S::Node,
+    goal: S::Node,
+    heuristic: impl Fn(&S, S::Node) -> usize,
+) -> Option<PathfindingResult<S::Node>> {
+    let frontier = &mut scratch.frontier;
+    let history = &mut scratch.history;
+    let cost_until = &mut scratch.cost_until;
+    let (mut pushed, mut expanded) = (1, 0);
+    frontier.push(PriorityState {
+        cost: 0,
+        tie: 0,
+        pos: start,
+    });
+    history.insert(start, start);
+    cost_until.insert(start, 0);
+
+    while let Some(current) = frontier.pop() {
+        if current.pos == goal {
+            break;
+        }
+        expanded += 1;
+        for neighbor in space.neighbors(current.pos) {
+            let new_cost = cost_until[&current.pos] + space.cost(current.pos, neighbor);
+            if cost_until
+                .get(&neighbor)
+                .is_none_or(|&cost| new_cost < cost)
+            {
+                cost_until.insert(neighbor, new_cost);
+                frontier.push(PriorityState {
+                    cost: new_cost + heuristic(space, neighbor),
+                    tie: tie_break.key(space, (start, goal), neighbor, new_cost, pushed),
+                    pos: neighbor,
+                });
+                pushed += 1;
+                history.insert(neighbor, current.pos);
+            }
+        }
+    }
+
+    construct_path(start, goal, history, cost_until, expanded)
+}
+
+/// Follows `history` back from `goal` and reports the cost the search accumulated in
+/// `cost_until`, which `PathfindingResult::validate` then checks against the path itself.
+pub fn construct_path<N: Copy + Eq + Hash>(
+    start: N,
+    goal: N,
+    history: &HashMap<N, N>,
+    cost_until: &HashMap<N, usize>,
+    nodes_expanded: usize,
+) -> Option<PathfindingResult<N>> {
+    let mut path = vec![goal];
+    let mut current = goal;
+    while current != start {
+        current = *history.get(&current)?;
+        path.push(current);
+    }
+    path.reverse();
+
+    Some(PathfindingResult {
+        cost_of_path: cost_until[&goal],
+        path,
+        nodes_visited: history.len(),
+        nodes_expanded,
+    })
+}
+
+/// Sum of the step costs along `path`. The start node itself is free. Steps a space can't take
+/// cost `usize::MAX`, so the sum saturates rather than wrapping around to something plausible.
+pub fn path_cost<S: SearchSpace>(space: &S, path: &[S::Node]) -> usize {
+    path.windows(2)
+        .map(|step| space.cost(step[0], step[1]))
+        .fold(0, usize::saturating_add)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::frontier::FrontierKind;
+    use crate::search::{self, TieBreak};
+    use crate::{Algorithm, Map, MapNode};
+
+    fn plateau(size: usize) -> Map {
+        let mut map = Map::new(size, size);
+        for node in map.grid.iter_mut().flatten() {
+            *node = MapNode::Path(1);
+        }
+        map
+    }
+
+    #[test]
+    fn test_tie_breaks_keep_paths_optimal() {
+        let map = plateau(12);
+        for algorithm in [Algorithm::Dijkstra, Algorithm::AStar] {
+            for tie_break in TieBreak::ALL {
+                let res = search::find_path_using(
+                    &map,
+                    FrontierKind::BinaryHeap,
+                    algorithm,
+                    tie_break,
+                    (0, 0),
+                    (11, 7),
+                )
+                .unwrap();
+                assert_eq!(res.cost_of_path, 18, "{:?} {:?}", algorithm, tie_break);
+            }
+        }
+    }
+
+    #[test]
+    fn test_every_frontier_honours_the_tie_break() {
+        let map = plateau(12);
+        for tie_break in TieBreak::ALL {
+            let run = |frontier| {
+                search::find_path_using(
+                    &map,
+                    frontier,
+                    Algorithm::AStar,
+                    tie_break,
+                    (0, 0),
+                    (11, 7),
+                )
+                .unwrap()
+            };
+            let heap = run(FrontierKind::BinaryHeap);
+            for frontier in [FrontierKind::Bucket, FrontierKind::Radix] {
+                let res = run(frontier);
+                assert_eq!(res.path, heap.path, "{:?} {:?}", frontier, tie_break);
+                assert_eq!(res.nodes_expanded, heap.nodes_expanded);
+            }
+        }
+    }
+
+    #[test]
+    fn test_deep_tie_breaks_expand_less_of_a_plateau() {
+        let map = plateau(20);
+        let expanded = |tie_break| {
+            search::find_path_using(
+                &map,
+                FrontierKind::BinaryHeap,
+                Algorithm::AStar,
+                tie_break,
+                (19, 19),
+                (0, 0),
+            )
+            .unwrap()
+            .nodes_expanded
+        };
+
+        let position = expanded(TieBreak::Position);
+        assert!(position > 38);
+        assert_eq!(expanded(TieBreak::HigherG), 38);
+        assert_eq!(expanded(TieBreak::LowerH), 38);
+        assert!(expanded(TieBreak::Fifo) > expanded(TieBreak::Lifo));
+    }
+
+    #[test]
+    fn test_cost_of_path_leaves_out_the_start_tile() {
+        let mut map = plateau(3);
+        map.grid[0][0] = MapNode::Path(5);
+        map.grid[0][1] = MapNode::Path(2);
+        map.grid[0][2] = MapNode::Path(3);
+
+        for algorithm in Algorithm::ALL {
+            let res = search::find_path(&map, algorithm, (0, 0), (0, 2)).unwrap();
+            assert_eq!(res.path, vec![(0, 0), (0, 1), (0, 2)], "{:?}", algorithm);
+            assert_eq!(res.cost_of_path, 5, "{:?}", algorithm);
+        }
+    }
+
+    #[test]
+    fn test_optimal_searches_handle_free_tiles() {
+        // The direct route costs 1, the detour around the wall is free.
+        let mut map = Map::new(3, 3);
+        map.grid[0][1] = MapNode::Path(1);
+        map.grid[1][1] = MapNode::VerticalObstacle;
+
+        for algorithm in [
+            Algorithm::Dijkstra,
+            Algorithm::AStar,
+            Algorithm::IdaStar,
+            Algorithm::Fringe,
+        ] {
+            let res = search::find_path(&map, algorithm, (0, 0), (0, 2)).unwrap();
+            assert_eq!(res.cost_of_path, 0, "{:?}", algorithm);
+        }
+    }
+
+    #[test]
+    fn test_cross_product_follows_the_straight_line() {
+        let map = plateau(20);
+        let res = search::find_path_using(
+            &map,
+            FrontierKind::BinaryHeap,
+            Algorithm::AStar,
+            TieBreak::CrossProduct,
+            (0, 0),
+            (10, 10),
+        )
+        .unwrap();
+        assert!(res.path.iter().all(|&(row, col)| (row - col).abs() <= 1));
+    }
+}
@@ -0,0 +1,249 @@
+use crate::space_time::ObstacleSchedule;
+use crate::Map;
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum Event {
+    /// The agent moved from `from` to `to`, or waited there if the two are the same cell.
+    /// `cost` is the tile cost paid for it.
+    Step {
+        tick: usize,
+        from: (i32, i32),
+        to: (i32, i32),
+        cost: usize,
+    },
+    /// The agent reached the end of its path, having paid `cost` on the way.
+    Arrived {
+        tick: usize,
+        pos: (i32, i32),
+        cost: usize,
+    },
+    /// The agent stayed at `pos` because `next` is impassable now.
+    Blocked {
+        tick: usize,
+        pos: (i32, i32),
+        next: (i32, i32),
+    },
+}
+
+type Callback<'a> = Box<dyn FnMut(&Map, &Event) + 'a>;
+
+/// Moves the agent of a map along a path, one step per tick. The map is handed to every tick
+/// rather than held on to, so that it can change in between: when the next cell stops being
+/// walkable, or the schedule has it occupied, the agent waits and a `Blocked` event fires
+/// instead, every tick until the cell frees up again.
+pub struct Simulation<'a> {
+    path: &'a [(i32, i32)],
+    schedule: Option<&'a ObstacleSchedule>,
+    subscribers: Vec<Callback<'a>>,
+    index: usize,
+    tick: usize,
+    cost: usize,
+    arrived: bool,
+}
+
+impl<'a> Simulation<'a> {
+    /// Follows `path`, usually the `path` of a `PathfindingResult`.
+    pub fn new(path: &'a [(i32, i32)]) -> Self {
+        assert!(!path.is_empty(), "Can't simulate an empty path");
+        Simulation {
+            path,
+            schedule: None,
+            subscribers: Vec::new(),
+            index: 0,
+            tick: 0,
+            cost: 0,
+            arrived: false,
+        }
+    }
+
+    /// Also treats cells the schedule has occupied as impassable.
+    pub fn with_schedule(mut self, schedule: &'a ObstacleSchedule) -> Self {
+        self.schedule = Some(schedule);
+        self
+    }
+
+    /// Calls `callback` with the map after every event.
+    pub fn subscribe(&mut self, callback: impl FnMut(&Map, &Event) + 'a) {
+        self.subscribers.push(Box::new(callback));
+    }
+
+    /// Advances the agent by one step, moving `map.agent_pos` along, and returns the events of
+    /// this tick: a step, possibly followed by the arrival, or a block. Nothing happens once the
+    /// agent has arrived.
+    pub fn tick(&mut self, map: &mut Map) -> Vec<Event> {
+        if self.arrived {
+            return Vec::new();
+        }
+        let pos = self.path[self.index];
+        map.agent_pos = pos;
+
+        let mut events = Vec::new();
+        if let Some(&next) = self.path.get(self.index + 1) {
+            let occupied = self
+                .schedule
+                .is_some_and(|schedule| schedule.is_occupied(next, self.tick + 1));
+            self.tick += 1;
+            if !map.is_walkable(next) || occupied {
+                events.push(Event::Blocked {
+                    tick: self.tick,
+                    pos,
+                    next,
+                });
+            } else {
+                let cost = if next == pos {
+                    0
+                } else {
+                    map.cost_adjacent(next)
+                };
+                map.agent_pos = next;
+                self.index += 1;
+                self.cost += cost;
+                events.push(Event::Step {
+                    tick: self.tick,
+                    from: pos,
+                    to: next,
+                    cost,
+                });
+            }
+        }
+        if self.index == self.path.len() - 1 {
+            self.arrived = true;
+            events.push(Event::Arrived {
+                tick: self.tick,
+                pos: self.path[self.index],
+                cost: self.cost,
+            });
+        }
+
+        for event in &events {
+            for subscriber in &mut self.subscribers {
+                subscriber(map, event);
+            }
+        }
+        events
+    }
+
+    /// Ticks until the agent arrives or is blocked, returning that event.
+    pub fn run(&mut self, map: &mut Map) -> Option<Event> {
+        loop {
+            match self.tick(map).last() {
+                None => return None,
+                Some(event @ (Event::Arrived { .. } | Event::Blocked { .. })) => {
+                    return Some(*event)
+                }
+                Some(Event::Step { .. }) => {}
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::simulation::{Event, Simulation};
+    use crate::space_time::ObstacleSchedule;
+    use crate::{Map, MapNode};
+    use std::cell::RefCell;
+
+    fn corridor() -> Map {
+        let mut map = Map::new(4, 1);
+        for (col, node) in map.grid[0].iter_mut().enumerate() {
+            *node = MapNode::Path(col as i32 + 1);
+        }
+        map
+    }
+
+    const PATH: [(i32, i32); 4] = [(0, 0), (0, 1), (0, 2), (0, 3)];
+
+    #[test]
+    fn test_steps_and_arrival() {
+        let mut map = corridor();
+        let seen = RefCell::new(Vec::new());
+        let mut simulation = Simulation::new(&PATH);
+        simulation.subscribe(|map, event| seen.borrow_mut().push((map.agent_pos, *event)));
+
+        assert_eq!(
+            simulation.tick(&mut map),
+            [Event::Step {
+                tick: 1,
+                from: (0, 0),
+                to: (0, 1),
+                cost: 2
+            }]
+        );
+        assert_eq!(map.agent_pos, (0, 1));
+        assert_eq!(
+            simulation.run(&mut map),
+            Some(Event::Arrived {
+                tick: 3,
+                pos: (0, 3),
+                cost: 9
+            })
+        );
+        assert!(simulation.tick(&mut map).is_empty());
+        drop(simulation);
+
+        let seen = seen.into_inner();
+        assert_eq!(seen.len(), 4);
+        assert!(seen.iter().all(|(agent_pos, event)| match event {
+            Event::Step { to, .. } => agent_pos == to,
+            _ => agent_pos == &(0, 3),
+        }));
+    }
+
+    #[test]
+    fn test_blocked_until_the_cell_frees_up() {
+        let mut map = corridor();
+        let mut simulation = Simulation::new(&PATH);
+        simulation.tick(&mut map);
+        map.grid[0][2] = MapNode::VerticalObstacle;
+
+        let blocked = Event::Blocked {
+            tick: 2,
+            pos: (0, 1),
+            next: (0, 2),
+        };
+        assert_eq!(simulation.run(&mut map), Some(blocked));
+        assert_eq!(map.agent_pos, (0, 1));
+
+        map.grid[0][2] = MapNode::Path(3);
+        assert!(matches!(
+            simulation.run(&mut map),
+            Some(Event::Arrived { tick: 4, .. })
+        ));
+    }
+
+    #[test]
+    fn test_schedule_and_waits() {
+        let mut map = corridor();
+        let mut schedule = ObstacleSchedule::new();
+        schedule.occupy((0, 1), 1);
+        let path = [(0, 0), (0, 0), (0, 1)];
+
+        let mut simulation = Simulation::new(&PATH).with_schedule(&schedule);
+        assert!(matches!(
+            simulation.tick(&mut map)[..],
+            [Event::Blocked { tick: 1, .. }]
+        ));
+
+        // Waiting a tick first, like a space-time path would, avoids the obstacle.
+        let mut simulation = Simulation::new(&path).with_schedule(&schedule);
+        assert_eq!(
+            simulation.run(&mut map),
+            Some(Event::Arrived {
+                tick: 2,
+                pos: (0, 1),
+                cost: 2
+            })
+        );
+
+        let mut simulation = Simulation::new(&[(0, 2)]);
+        assert!(matches!(
+            simulation.tick(&mut map)[..],
+            [Event::Arrived {
+                tick: 0,
+                cost: 0,
+                ..
+            }]
+        ));
+    }
+}
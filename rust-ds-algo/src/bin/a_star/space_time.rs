@@ -0,0 +1,365 @@
+use crate::search::SearchSpace;
+use crate::validate::{check_endpoints, PathViolation};
+use crate::Map;
+use std::cmp::Ordering;
+use std::collections::{BinaryHeap, HashMap, HashSet};
+
+type TimedState = ((i32, i32), usize);
+
+/// An obstacle that cycles through `route`, occupying `route[t % route.len()]` at time step `t`.
+#[derive(Clone, Debug)]
+pub struct Patrol {
+    route: Vec<(i32, i32)>,
+}
+
+impl Patrol {
+    pub fn new(route: Vec<(i32, i32)>) -> Self {
+        assert!(!route.is_empty(), "Patrol route must not be empty");
+        Patrol { route }
+    }
+
+    /// Walks `cells` to the end and back again, e.g. a guard pacing a corridor.
+    pub fn back_and_forth(cells: &[(i32, i32)]) -> Self {
+        let mut route = cells.to_vec();
+        if cells.len() > 2 {
+            route.extend(cells[1..cells.len() - 1].iter().rev());
+        }
+        Patrol::new(route)
+    }
+
+    pub fn position_at(&self, time: usize) -> (i32, i32) {
+        self.route[time % self.route.len()]
+    }
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct ObstacleSchedule {
+    occupied: HashSet<TimedState>,
+    patrols: Vec<Patrol>,
+}
+
+impl ObstacleSchedule {
+    pub fn new() -> Self {
+        ObstacleSchedule::default()
+    }
+
+    pub fn occupy(&mut self, cell: (i32, i32), time: usize) {
+        self.occupied.insert((cell, time));
+    }
+
+    pub fn add_patrol(&mut self, patrol: Patrol) {
+        self.patrols.push(patrol);
+    }
+
+    pub fn is_occupied(&self, cell: (i32, i32), time: usize) -> bool {
+        self.occupied.contains(&(cell, time))
+            || self.patrols.iter().any(|p| p.position_at(time) == cell)
+    }
+
+    pub fn occupied_at(&self, time: usize) -> Vec<(i32, i32)> {
+        let mut cells = self
+            .occupied
+            .iter()
+            .filter(|(_, t)| *t == time)
+            .map(|(cell, _)| *cell)
+            .chain(self.patrols.iter().map(|p| p.position_at(time)))
+            .collect::<Vec<_>>();
+        cells.sort_unstable();
+        cells.dedup();
+        cells
+    }
+
+    /// A move from `from` to `to` starting at `time` is blocked if the target is taken on arrival,
+    /// or if a patrol walks the same edge the opposite way (the two would swap cells).
+    fn blocks_move(&self, from: (i32, i32), to: (i32, i32), time: usize) -> bool {
+        self.is_occupied(to, time + 1)
+            || (from != to
+                && self
+                    .patrols
+                    .iter()
+                    .any(|p| p.position_at(time) == to && p.position_at(time + 1) == from))
+    }
+}
+
+pub struct TimedPathfindingResult {
+    pub nodes_visited: usize,
+    pub cost_of_path: usize,
+    pub path: Vec<TimedState>,
+}
+
+impl TimedPathfindingResult {
+    /// The agent's cell at every time step, starting at `t = 0`.
+    pub fn positions(&self) -> Vec<(i32, i32)> {
+        self.path.iter().map(|(pos, _)| *pos).collect()
+    }
+
+    /// Checks that the agent goes from its position on `map` to `goal`, one time step per state,
+    /// either waiting or moving to a walkable neighbor without running into `schedule`, and that
+    /// the path costs what it claims to with every wait costing `wait_cost`.
+    pub fn validate(
+        &self,
+        map: &Map,
+        goal: (i32, i32),
+        schedule: &ObstacleSchedule,
+        wait_cost: usize,
+    ) -> Result<(), PathViolation<(i32, i32)>> {
+        let positions = self.positions();
+        check_endpoints(&positions, map.agent_pos, goal)?;
+        if schedule.is_occupied(map.agent_pos, 0) {
+            return Err(PathViolation::Collides {
+                step: 0,
+                node: map.agent_pos,
+            });
+        }
+
+        let mut actual = 0usize;
+        for (i, step) in self.path.windows(2).enumerate() {
+            let ((from, time), (to, next_time)) = (step[0], step[1]);
+            if from != to && !map.is_walkable(to) {
+                return Err(PathViolation::Blocked {
+                    step: i + 1,
+                    node: to,
+                });
+            }
+            if (from != to && !map.is_adjacent(from, to)) || next_time != time + 1 {
+                return Err(PathViolation::NotAdjacent {
+                    step: i + 1,
+                    from,
+                    to,
+                });
+            }
+            if schedule.blocks_move(from, to, time) {
+                return Err(PathViolation::Collides {
+                    step: i + 1,
+                    node: to,
+                });
+            }
+            let cost = if from == to {
+                wait_cost
+            } else {
+                map.cost_adjacent(to)
+            };
+            actual = actual.saturating_add(cost);
+        }
+
+        if actual != self.cost_of_path {
+            return Err(PathViolation::CostMismatch {
+                reported: self.cost_of_path,
+                actual,
+            });
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, Eq, PartialEq)]
+struct TimedPriorityState {
+    cost: usize,
+    pos: (i32, i32),
+    time: usize,
+}
+
+impl PartialOrd<Self> for TimedPriorityState {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for TimedPriorityState {
+    fn cmp(&self, other: &Self) -> Ordering {
+        other
+            .cost
+            .cmp(&self.cost)
+            .then_with(|| self.time.cmp(&other.time))
+            .then_with(|| self.pos.cmp(&other.pos))
+    }
+}
+
+impl Map {
+    /// A* over (cell, time step) states. Every step the agent either moves to a neighbor, paying
+    /// its tile cost, or waits in place for `wait_cost`. States later than `max_time` are not
+    /// expanded, which bounds the search when the goal is unreachable. Waiting never brings the
+    /// goal closer, so the distance times the cheapest tile stays a lower bound.
+    pub fn find_path_space_time(
+        &self,
+        goal: (i32, i32),
+        schedule: &ObstacleSchedule,
+        wait_cost: usize,
+        max_time: usize,
+    ) -> Option<TimedPathfindingResult> {
+        let start = (self.agent_pos, 0);
+        if schedule.is_occupied(self.agent_pos, 0) {
+            return None;
+        }
+
+        let cheapest_tile = self.cheapest_step();
+        let mut frontier = BinaryHeap::new();
+        frontier.push(TimedPriorityState {
+            cost: 0,
+            pos: self.agent_pos,
+            time: 0,
+        });
+        let mut history = HashMap::new();
+        history.insert(start, start);
+        let mut cost_until = HashMap::new();
+        cost_until.insert(start, 0);
+
+        while let Some(current) = frontier.pop() {
+            let state = (current.pos, current.time);
+            if current.pos == goal {
+                let res = self.construct_timed_path(state, &history, &cost_until);
+                if cfg!(debug_assertions) {
+                    if let Err(violation) = res.validate(self, goal, schedule, wait_cost) {
+                        panic!("Space-time A* returned an invalid path: {}", violation);
+                    }
+                }
+                return Some(res);
+            }
+            if current.time >= max_time {
+                continue;
+            }
+
+            let moves = self
+                .neighbors(current.pos)
+                .map(|n| (n, self.cost_adjacent(n)))
+                .chain(std::iter::once((current.pos, wait_cost)));
+            for (next, step_cost) in moves {
+                if schedule.blocks_move(current.pos, next, current.time) {
+                    continue;
+                }
+                let next_state = (next, current.time + 1);
+                let new_cost = cost_until[&state] + step_cost;
+                if cost_until
+                    .get(&next_state)
+                    .is_none_or(|&cost| new_cost < cost)
+                {
+                    cost_until.insert(next_state, new_cost);
+                    frontier.push(TimedPriorityState {
+                        cost: new_cost + self.heuristic(goal, next) * cheapest_tile,
+                        pos: next,
+                        time: next_state.1,
+                    });
+                    history.insert(next_state, state);
+                }
+            }
+        }
+
+        None
+    }
+
+    fn construct_timed_path(
+        &self,
+        goal: TimedState,
+        history: &HashMap<TimedState, TimedState>,
+        cost_until: &HashMap<TimedState, usize>,
+    ) -> TimedPathfindingResult {
+        let mut path = vec![goal];
+        let mut next = goal;
+        while next.1 != 0 {
+            next = history[&next];
+            path.push(next);
+        }
+        path.reverse();
+
+        TimedPathfindingResult {
+            nodes_visited: history.len(),
+            cost_of_path: cost_until[&goal],
+            path,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::space_time::{ObstacleSchedule, Patrol};
+    use crate::validate::PathViolation;
+    use crate::{Map, MapNode};
+
+    fn corridor(length: usize) -> Map {
+        let mut map = Map::new(length, 3);
+        for col in 0..length {
+            map.grid[0][col] = MapNode::HorizontalObstacle;
+            map.grid[2][col] = MapNode::HorizontalObstacle;
+            map.grid[1][col] = MapNode::Path(1);
+        }
+        map.agent_pos = (1, 0);
+        map
+    }
+
+    #[test]
+    fn test_free_corridor_needs_no_waiting() {
+        let map = corridor(5);
+        let res = map
+            .find_path_space_time((1, 4), &ObstacleSchedule::new(), 1, 20)
+            .unwrap();
+
+        assert_eq!(
+            res.positions(),
+            vec![(1, 0), (1, 1), (1, 2), (1, 3), (1, 4)]
+        );
+        assert_eq!(res.cost_of_path, 4);
+    }
+
+    #[test]
+    fn test_waits_for_scheduled_obstacle() {
+        let map = corridor(5);
+        let mut schedule = ObstacleSchedule::new();
+        schedule.occupy((1, 1), 1);
+        schedule.occupy((1, 1), 2);
+
+        let res = map.find_path_space_time((1, 2), &schedule, 1, 20).unwrap();
+
+        assert_eq!(
+            res.positions(),
+            vec![(1, 0), (1, 0), (1, 0), (1, 1), (1, 2)]
+        );
+        assert_eq!(res.path.last(), Some(&((1, 2), 4)));
+        assert_eq!(res.cost_of_path, 4);
+    }
+
+    #[test]
+    fn test_does_not_swap_with_patrol() {
+        let map = corridor(3);
+        let mut schedule = ObstacleSchedule::new();
+        schedule.add_patrol(Patrol::new(vec![(1, 1), (1, 0)]));
+
+        assert!(map.find_path_space_time((1, 2), &schedule, 1, 20).is_none());
+    }
+
+    #[test]
+    fn test_unreachable_within_horizon() {
+        let map = corridor(5);
+        let mut schedule = ObstacleSchedule::new();
+        for t in 0..=10 {
+            schedule.occupy((1, 2), t);
+        }
+
+        assert!(map.find_path_space_time((1, 4), &schedule, 1, 10).is_none());
+    }
+
+    #[test]
+    fn test_validate_checks_the_schedule() {
+        let map = corridor(5);
+        let mut schedule = ObstacleSchedule::new();
+        schedule.occupy((1, 1), 1);
+        let res = map.find_path_space_time((1, 2), &schedule, 3, 20).unwrap();
+        assert_eq!(res.validate(&map, (1, 2), &schedule, 3), Ok(()));
+        assert_eq!(res.cost_of_path, 5);
+
+        assert_eq!(
+            res.validate(&map, (1, 2), &schedule, 1),
+            Err(PathViolation::CostMismatch {
+                reported: 5,
+                actual: 3
+            })
+        );
+        schedule.occupy((1, 1), 2);
+        assert_eq!(
+            res.validate(&map, (1, 2), &schedule, 3),
+            Err(PathViolation::Collides {
+                step: 2,
+                node: (1, 1)
+            })
+        );
+    }
+}
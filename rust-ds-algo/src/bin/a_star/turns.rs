@@ -0,0 +1,556 @@
+use crate::search::{PriorityState, SearchSpace};
+use crate::validate::{check_endpoints, PathViolation};
+use crate::{Map, PathfindingResult};
+use serde::Serialize;
+use std::collections::{BinaryHeap, HashMap};
+use std::str::FromStr;
+
+/// Directions a vehicle can face, counterclockwise from east. The first four are the ones
+/// `Map::neighbors` steps in.
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash, Ord, PartialOrd, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Heading {
+    East,
+    North,
+    West,
+    South,
+    NorthEast,
+    NorthWest,
+    SouthWest,
+    SouthEast,
+}
+
+impl Heading {
+    pub const ALL: [Heading; 8] = [
+        Heading::East,
+        Heading::North,
+        Heading::West,
+        Heading::South,
+        Heading::NorthEast,
+        Heading::NorthWest,
+        Heading::SouthWest,
+        Heading::SouthEast,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Heading::East => "east",
+            Heading::North => "north",
+            Heading::West => "west",
+            Heading::South => "south",
+            Heading::NorthEast => "north-east",
+            Heading::NorthWest => "north-west",
+            Heading::SouthWest => "south-west",
+            Heading::SouthEast => "south-east",
+        }
+    }
+
+    pub fn arrow(&self) -> &'static str {
+        match self {
+            Heading::East => "→",
+            Heading::North => "↑",
+            Heading::West => "←",
+            Heading::South => "↓",
+            Heading::NorthEast => "↗",
+            Heading::NorthWest => "↖",
+            Heading::SouthWest => "↙",
+            Heading::SouthEast => "↘",
+        }
+    }
+
+    /// (row, col) offset of a step in this direction.
+    fn delta(self) -> (i32, i32) {
+        match self {
+            Heading::East => (0, 1),
+            Heading::North => (-1, 0),
+            Heading::West => (0, -1),
+            Heading::South => (1, 0),
+            Heading::NorthEast => (-1, 1),
+            Heading::NorthWest => (-1, -1),
+            Heading::SouthWest => (1, -1),
+            Heading::SouthEast => (1, 1),
+        }
+    }
+
+    fn step(self, (row, col): (i32, i32)) -> (i32, i32) {
+        let (dr, dc) = self.delta();
+        (row + dr, col + dc)
+    }
+
+    fn is_reverse_of(self, other: Heading) -> bool {
+        let (a, b) = (self.delta(), other.delta());
+        (a.0 + b.0, a.1 + b.1) == (0, 0)
+    }
+
+    /// Whether driving straight ahead from `from` passes through `to`.
+    fn points_at(self, from: (i32, i32), to: (i32, i32)) -> bool {
+        let (dr, dc) = self.delta();
+        let (rows, cols) = (to.0 - from.0, to.1 - from.1);
+        let steps = if dr != 0 { rows * dr } else { cols * dc };
+        steps > 0 && (rows, cols) == (dr * steps, dc * steps)
+    }
+}
+
+impl FromStr for Heading {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Heading::ALL
+            .iter()
+            .find(|heading| heading.name() == s.to_lowercase())
+            .copied()
+            .ok_or(format!("Unknown heading: {}", s))
+    }
+}
+
+/// What changing direction costs on top of the tile costs. Any change of heading costs `turn`,
+/// except for turning around, which costs `reverse`.
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub struct Vehicle {
+    pub turn: usize,
+    pub reverse: usize,
+    /// Also drive diagonally. Corners can't be cut, like in `VoxelMap`.
+    pub diagonal: bool,
+}
+
+impl Vehicle {
+    fn headings(&self) -> &'static [Heading] {
+        if self.diagonal {
+            &Heading::ALL
+        } else {
+            &Heading::ALL[..4]
+        }
+    }
+
+    fn turn_cost(&self, from: Option<Heading>, to: Heading) -> usize {
+        match from {
+            Some(from) if from == to => 0,
+            Some(from) if from.is_reverse_of(to) => self.reverse,
+            Some(_) => self.turn,
+            None => 0,
+        }
+    }
+}
+
+/// A position together with the direction the vehicle faces there. Only the start may have no
+/// heading, which lets the first step go anywhere for free.
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash, Ord, PartialOrd)]
+struct Pose {
+    pos: (i32, i32),
+    heading: Option<Heading>,
+}
+
+/// A path found by `Map::find_path_turning`. `headings[i]` is the direction of the step from
+/// `path[i]` to `path[i + 1]`, and `cost_of_path` includes the turn costs.
+#[derive(Debug, Clone, Serialize)]
+pub struct OrientedPath {
+    #[serde(flatten)]
+    pub result: PathfindingResult,
+    pub headings: Vec<Heading>,
+    pub turns: usize,
+    pub reversals: usize,
+}
+
+impl OrientedPath {
+    /// The map with each step drawn as an arrow in its direction.
+    pub fn draw_on(&self, map: &Map) -> Map {
+        let mut map = map.clone();
+        for (&node, heading) in self.result.path[1..].iter().zip(&self.headings) {
+            map.draw(node, heading.arrow());
+        }
+        map
+    }
+
+    /// Checks that the vehicle drives from its position on `map` to `goal`, every step going the
+    /// way `headings` says without leaving the walkable tiles or cutting a corner, and that the
+    /// path costs what it claims to: the tiles plus the changes of heading, starting from
+    /// `heading`.
+    pub fn validate(
+        &self,
+        map: &Map,
+        goal: (i32, i32),
+        heading: Option<Heading>,
+        vehicle: &Vehicle,
+    ) -> Result<(), PathViolation<(i32, i32)>> {
+        let path = &self.result.path;
+        check_endpoints(path, map.agent_pos, goal)?;
+
+        let (mut actual, mut facing) = (0usize, heading);
+        for (i, step) in path.windows(2).enumerate() {
+            let (from, to) = (step[0], step[1]);
+            let heading = match self.headings.get(i) {
+                Some(&heading)
+                    if vehicle.headings().contains(&heading) && heading.step(from) == to =>
+                {
+                    heading
+                }
+                _ => {
+                    return Err(PathViolation::NotAdjacent {
+                        step: i + 1,
+                        from,
+                        to,
+                    })
+                }
+            };
+            let (dr, dc) = heading.delta();
+            let blocked =
+                IntoIterator::into_iter([to, (from.0 + dr, from.1), (from.0, from.1 + dc)])
+                    .find(|&node| !map.is_walkable(node));
+            if let Some(node) = blocked {
+                return Err(PathViolation::Blocked { step: i + 1, node });
+            }
+            actual = actual
+                .saturating_add(map.cost_adjacent(to))
+                .saturating_add(vehicle.turn_cost(facing, heading));
+            facing = Some(heading);
+        }
+
+        if actual != self.result.cost_of_path {
+            return Err(PathViolation::CostMismatch {
+                reported: self.result.cost_of_path,
+                actual,
+            });
+        }
+        Ok(())
+    }
+}
+
+impl Map {
+    /// A* over (position, heading) states from the agent to `goal`, arriving in any heading. The
+    /// agent starts facing `heading`, or whichever way its first step goes when not given.
+    ///
+    /// The heuristic is the Manhattan distance, or the octile one with diagonal moves, times the
+    /// cheapest tile, plus the cheapest change of heading unless the goal lies straight ahead.
+    /// Both parts are lower bounds, so the path is optimal. The heuristic is not consistent
+    /// though, so states are reopened when reached more cheaply.
+    pub fn find_path_turning(
+        &self,
+        goal: (i32, i32),
+        heading: Option<Heading>,
+        vehicle: &Vehicle,
+    ) -> Option<OrientedPath> {
+        let cheapest_tile = self.cheapest_step();
+        let heuristic = |pose: Pose| {
+            let (rows, cols) = (
+                (pose.pos.0 - goal.0).unsigned_abs() as usize,
+                (pose.pos.1 - goal.1).unsigned_abs() as usize,
+            );
+            let distance = if vehicle.diagonal {
+                rows.max(cols)
+            } else {
+                rows + cols
+            };
+            let turning = match pose.heading {
+                Some(heading) if pose.pos != goal && !heading.points_at(pose.pos, goal) => {
+                    vehicle.turn.min(vehicle.reverse)
+                }
+                _ => 0,
+            };
+            distance * cheapest_tile + turning
+        };
+
+        let start = Pose {
+            pos: self.agent_pos,
+            heading,
+        };
+        let mut frontier = BinaryHeap::new();
+        let mut history = HashMap::new();
+        let mut cost_until = HashMap::new();
+        let mut expanded = 0;
+        frontier.push(PriorityState {
+            cost: heuristic(start),
+            tie: 0,
+            pos: start,
+        });
+        cost_until.insert(start, 0);
+
+        while let Some(current) = frontier.pop() {
+            let pose = current.pos;
+            let cost = cost_until[&pose];
+            if current.cost > cost + heuristic(pose) {
+                continue;
+            }
+            if pose.pos == goal {
+                let res = self.oriented_path(start, pose, &history, cost, expanded);
+                if cfg!(debug_assertions) {
+                    if let Err(violation) = res.validate(self, goal, heading, vehicle) {
+                        panic!("Turning A* returned an invalid path: {}", violation);
+                    }
+                }
+                return Some(res);
+            }
+            expanded += 1;
+
+            for &heading in vehicle.headings() {
+                let next = heading.step(pose.pos);
+                let (dr, dc) = heading.delta();
+                let cuts_corner = dr != 0
+                    && dc != 0
+                    && !(self.is_walkable((pose.pos.0 + dr, pose.pos.1))
+                        && self.is_walkable((pose.pos.0, pose.pos.1 + dc)));
+                if !self.is_walkable(next) || cuts_corner {
+                    continue;
+                }
+
+                let next = Pose {
+                    pos: next,
+                    heading: Some(heading),
+                };
+                let new_cost =
+                    cost + self.cost_adjacent(next.pos) + vehicle.turn_cost(pose.heading, heading);
+                if cost_until
+                    .get(&next)
+                    .is_some_and(|&known| known <= new_cost)
+                {
+                    continue;
+                }
+                cost_until.insert(next, new_cost);
+                history.insert(next, pose);
+                frontier.push(PriorityState {
+                    cost: new_cost + heuristic(next),
+                    tie: 0,
+                    pos: next,
+                });
+            }
+        }
+
+        None
+    }
+
+    fn oriented_path(
+        &self,
+        start: Pose,
+        end: Pose,
+        history: &HashMap<Pose, Pose>,
+        cost: usize,
+        expanded: usize,
+    ) -> OrientedPath {
+        let mut poses = vec![end];
+        let mut current = end;
+        while current != start {
+            current = history[&current];
+            poses.push(current);
+        }
+        poses.reverse();
+
+        let headings = poses[1..]
+            .iter()
+            .filter_map(|pose| pose.heading)
+            .collect::<Vec<_>>();
+        let changes = poses
+            .windows(2)
+            .filter_map(|w| Some((w[0].heading?, w[1].heading?)))
+            .filter(|(from, to)| from != to);
+        let (reversals, turns): (Vec<_>, Vec<_>) =
+            changes.partition(|(from, to)| from.is_reverse_of(*to));
+
+        OrientedPath {
+            result: PathfindingResult {
+                nodes_visited: history.len() + 1,
+                nodes_expanded: expanded,
+                cost_of_path: cost,
+                path: poses.iter().map(|pose| pose.pos).collect(),
+            },
+            headings,
+            turns: turns.len(),
+            reversals: reversals.len(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::search;
+    use crate::turns::{Heading, Vehicle};
+    use crate::validate::PathViolation;
+    use crate::{Map, MapNode};
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+    use std::collections::HashMap;
+
+    fn open_map(width: usize, height: usize) -> Map {
+        let mut map = Map::new(width, height);
+        for node in map.grid.iter_mut().flatten() {
+            *node = MapNode::Path(1);
+        }
+        map
+    }
+
+    /// Cheapest cost to `goal` over all (position, heading) states, relaxing until nothing
+    /// changes.
+    fn brute_force(
+        map: &Map,
+        goal: (i32, i32),
+        heading: Option<Heading>,
+        vehicle: &Vehicle,
+    ) -> Option<usize> {
+        let mut best = HashMap::new();
+        best.insert((map.agent_pos, heading), 0);
+        let mut changed = true;
+        while changed {
+            changed = false;
+            for ((pos, from), cost) in best.clone() {
+                for &to in vehicle.headings() {
+                    let next = to.step(pos);
+                    let (dr, dc) = to.delta();
+                    if !map.is_walkable(next)
+                        || !map.is_walkable((pos.0 + dr, pos.1))
+                        || !map.is_walkable((pos.0, pos.1 + dc))
+                    {
+                        continue;
+                    }
+                    let cost = cost + map.cost_adjacent(next) + vehicle.turn_cost(from, to);
+                    let known = best.entry((next, Some(to))).or_insert(usize::MAX);
+                    if cost < *known {
+                        *known = cost;
+                        changed = true;
+                    }
+                }
+            }
+        }
+        best.iter()
+            .filter(|((pos, _), _)| *pos == goal)
+            .map(|(_, &cost)| cost)
+            .min()
+    }
+
+    #[test]
+    fn test_free_turns_match_dijkstra() {
+        let mut map = Map::new(15, 15);
+        map.generate_map(&mut StdRng::seed_from_u64(3), (10, 20));
+        map.grid[0][0] = MapNode::Path(1);
+        let vehicle = Vehicle {
+            turn: 0,
+            reverse: 0,
+            diagonal: false,
+        };
+
+        for goal in [(14, 14), (7, 3), (0, 12)] {
+            let plain = search::dijkstra(&map, (0, 0), goal).map(|res| res.cost_of_path);
+            let turning = map
+                .find_path_turning(goal, Some(Heading::South), &vehicle)
+                .map(|res| res.result.cost_of_path);
+            assert_eq!(turning, plain);
+        }
+    }
+
+    #[test]
+    fn test_turn_costs_stay_optimal() {
+        for seed in 0..6 {
+            let mut map = Map::new(9, 9);
+            map.generate_map(&mut StdRng::seed_from_u64(seed), (5, 15));
+            map.grid[0][0] = MapNode::Path(1);
+            for diagonal in [false, true] {
+                for heading in [None, Some(Heading::West)] {
+                    let vehicle = Vehicle {
+                        turn: 4,
+                        reverse: 7,
+                        diagonal,
+                    };
+                    let res = map.find_path_turning((8, 8), heading, &vehicle);
+                    assert_eq!(
+                        res.as_ref().map(|res| res.result.cost_of_path),
+                        brute_force(&map, (8, 8), heading, &vehicle),
+                        "seed {} diagonal {} heading {:?}",
+                        seed,
+                        diagonal,
+                        heading
+                    );
+                    if let Some(res) = res {
+                        assert_eq!(res.headings.len(), res.result.path.len() - 1);
+                        let turning = res.turns * vehicle.turn + res.reversals * vehicle.reverse;
+                        assert_eq!(
+                            res.result.cost_of_path,
+                            search::path_cost(&map, &res.result.path) + turning
+                        );
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_expensive_turns_prefer_straight_lines() {
+        let map = open_map(6, 6);
+        let vehicle = Vehicle {
+            turn: 10,
+            reverse: 100,
+            diagonal: false,
+        };
+
+        let res = map
+            .find_path_turning((5, 5), Some(Heading::East), &vehicle)
+            .unwrap();
+        assert_eq!(res.turns, 1);
+        assert_eq!(res.reversals, 0);
+        assert_eq!(res.result.cost_of_path, 20);
+        assert_eq!(res.headings[..5], [Heading::East; 5]);
+        assert_eq!(res.headings[5..], [Heading::South; 5]);
+
+        // Facing away from the goal, turning around once beats two turns.
+        let vehicle = Vehicle {
+            reverse: 5,
+            ..vehicle
+        };
+        let res = map
+            .find_path_turning((0, 0), None, &vehicle)
+            .map(|res| res.result.cost_of_path);
+        assert_eq!(res, Some(0));
+        let mut map = map;
+        map.agent_pos = (0, 3);
+        let res = map
+            .find_path_turning((0, 0), Some(Heading::East), &vehicle)
+            .unwrap();
+        assert_eq!((res.turns, res.reversals), (0, 1));
+        assert_eq!(res.result.cost_of_path, 8);
+        assert_eq!(res.draw_on(&map).render().matches('←').count(), 3);
+    }
+
+    #[test]
+    fn test_validate_counts_the_turns() {
+        let mut map = open_map(6, 6);
+        map.grid[1][1] = MapNode::VerticalObstacle;
+        let vehicle = Vehicle {
+            turn: 10,
+            reverse: 100,
+            diagonal: true,
+        };
+        let goal = (5, 5);
+        let mut res = map
+            .find_path_turning(goal, Some(Heading::East), &vehicle)
+            .unwrap();
+        assert_eq!(
+            res.validate(&map, goal, Some(Heading::East), &vehicle),
+            Ok(())
+        );
+
+        // Starting out facing another way costs a turn more than reported.
+        assert_eq!(
+            res.validate(&map, goal, Some(Heading::North), &vehicle),
+            Err(PathViolation::CostMismatch {
+                reported: res.result.cost_of_path,
+                actual: res.result.cost_of_path + 10
+            })
+        );
+
+        // The step onto (1, 1) cuts the corner of the obstacle even when driving around it.
+        res.result.path = vec![(0, 0), (1, 1)];
+        res.headings = vec![Heading::SouthEast];
+        assert_eq!(
+            res.validate(&map, (1, 1), Some(Heading::East), &vehicle),
+            Err(PathViolation::Blocked {
+                step: 1,
+                node: (1, 1)
+            })
+        );
+        res.headings = vec![Heading::South];
+        assert_eq!(
+            res.validate(&map, (1, 1), Some(Heading::East), &vehicle),
+            Err(PathViolation::NotAdjacent {
+                step: 1,
+                from: (0, 0),
+                to: (1, 1)
+            })
+        );
+    }
+}
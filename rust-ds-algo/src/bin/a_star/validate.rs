@@ -0,0 +1,230 @@
+use crate::search::SearchSpace;
+use crate::PathfindingResult;
+use std::fmt::{self, Debug, Display};
+
+/// What is wrong with a path, see `PathfindingResult::validate`. `step` counts from 1 for the
+/// step leaving the start.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub enum PathViolation<N> {
+    Empty,
+    WrongStart { expected: N, found: N },
+    WrongGoal { expected: N, found: N },
+    Blocked { step: usize, node: N },
+    NotAdjacent { step: usize, from: N, to: N },
+    Collides { step: usize, node: N },
+    CostMismatch { reported: usize, actual: usize },
+}
+
+impl<N: Debug> Display for PathViolation<N> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PathViolation::Empty => write!(f, "the path is empty"),
+            PathViolation::WrongStart { expected, found } => {
+                write!(
+                    f,
+                    "the path starts at {:?} instead of {:?}",
+                    found, expected
+                )
+            }
+            PathViolation::WrongGoal { expected, found } => {
+                write!(f, "the path ends at {:?} instead of {:?}", found, expected)
+            }
+            PathViolation::Blocked { step, node } => {
+                write!(f, "step {} enters the obstacle at {:?}", step, node)
+            }
+            PathViolation::NotAdjacent { step, from, to } => {
+                write!(f, "step {} jumps from {:?} to {:?}", step, from, to)
+            }
+            PathViolation::Collides { step, node } => {
+                write!(f, "step {} runs into a moving obstacle at {:?}", step, node)
+            }
+            PathViolation::CostMismatch { reported, actual } => write!(
+                f,
+                "the path is reported to cost {} but costs {}",
+                reported, actual
+            ),
+        }
+    }
+}
+
+/// Checks that a non-empty `path` leads from `start` to `goal`. Searches whose steps aren't
+/// those of a `SearchSpace` check the steps in between themselves.
+pub fn check_endpoints<N: Copy + Eq>(
+    path: &[N],
+    start: N,
+    goal: N,
+) -> Result<(), PathViolation<N>> {
+    let (first, last) = match (path.first(), path.last()) {
+        (Some(&first), Some(&last)) => (first, last),
+        _ => return Err(PathViolation::Empty),
+    };
+    if first != start {
+        return Err(PathViolation::WrongStart {
+            expected: start,
+            found: first,
+        });
+    }
+    if last != goal {
+        return Err(PathViolation::WrongGoal {
+            expected: goal,
+            found: last,
+        });
+    }
+    Ok(())
+}
+
+impl<N: Copy + Eq> PathfindingResult<N> {
+    /// Checks that the path leads from `start` to `goal` in single steps through passable nodes
+    /// of `space` and costs what it claims to, returning the first violation found. Endpoints
+    /// are checked first, then the steps in order, then the cost.
+    ///
+    /// The cost is summed up here step by step rather than taken from `search::path_cost`, so
+    /// that a search reporting the cost it accumulated is checked against something else.
+    pub fn validate<S: SearchSpace<Node = N>>(
+        &self,
+        space: &S,
+        start: N,
+        goal: N,
+    ) -> Result<(), PathViolation<N>> {
+        check_endpoints(&self.path, start, goal)?;
+
+        let mut actual = 0usize;
+        for (i, step) in self.path.windows(2).enumerate() {
+            let (from, to) = (step[0], step[1]);
+            if !space.is_passable(to) {
+                return Err(PathViolation::Blocked {
+                    step: i + 1,
+                    node: to,
+                });
+            }
+            if !space.is_adjacent(from, to) {
+                return Err(PathViolation::NotAdjacent {
+                    step: i + 1,
+                    from,
+                    to,
+                });
+            }
+            actual = actual.saturating_add(space.cost(from, to));
+        }
+
+        if actual != self.cost_of_path {
+            return Err(PathViolation::CostMismatch {
+                reported: self.cost_of_path,
+                actual,
+            });
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::search;
+    use crate::validate::PathViolation;
+    use crate::{Algorithm, Map, MapNode, PathfindingResult};
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+
+    fn result(path: Vec<(i32, i32)>, cost_of_path: usize) -> PathfindingResult {
+        PathfindingResult {
+            nodes_visited: 0,
+            nodes_expanded: 0,
+            cost_of_path,
+            path,
+        }
+    }
+
+    fn corridor() -> Map {
+        let mut map = Map::new(4, 2);
+        for (col, node) in map.grid[0].iter_mut().enumerate() {
+            *node = MapNode::Path(col as i32 + 1);
+        }
+        map.grid[1][1] = MapNode::VerticalObstacle;
+        map
+    }
+
+    #[test]
+    fn test_found_paths_are_valid() {
+        let mut map = Map::new(15, 15);
+        map.generate_map(&mut StdRng::seed_from_u64(12), (10, 20));
+        // Keep the top row and the right column open, so that the goal is always reachable.
+        for (row, tiles) in map.grid.iter_mut().enumerate() {
+            for (col, node) in tiles.iter_mut().enumerate() {
+                if (row == 0 || col == 14) && !matches!(node, MapNode::Path(_)) {
+                    *node = MapNode::Path(1);
+                }
+            }
+        }
+        for algorithm in Algorithm::DEFAULT {
+            let res = search::find_path(&map, algorithm, (0, 0), (14, 14))
+                .expect("the fixture has a path");
+            assert_eq!(res.validate(&map, (0, 0), (14, 14)), Ok(()));
+        }
+    }
+
+    #[test]
+    fn test_reports_the_first_violation() {
+        let map = corridor();
+        let (start, goal) = ((0, 0), (0, 3));
+        let valid = result(vec![(0, 0), (0, 1), (0, 2), (0, 3)], 9);
+        assert_eq!(valid.validate(&map, start, goal), Ok(()));
+
+        let check = |path: Vec<(i32, i32)>, cost| result(path, cost).validate(&map, start, goal);
+        assert_eq!(check(vec![], 0), Err(PathViolation::Empty));
+        assert_eq!(
+            check(vec![(1, 0), (0, 0), (0, 1)], 2),
+            Err(PathViolation::WrongStart {
+                expected: (0, 0),
+                found: (1, 0)
+            })
+        );
+        assert_eq!(
+            check(vec![(0, 0), (0, 1)], 2),
+            Err(PathViolation::WrongGoal {
+                expected: (0, 3),
+                found: (0, 1)
+            })
+        );
+        assert_eq!(
+            check(
+                vec![(0, 0), (1, 0), (1, 1), (0, 1), (0, 2), (0, 5), (0, 3)],
+                9
+            ),
+            Err(PathViolation::Blocked {
+                step: 2,
+                node: (1, 1)
+            })
+        );
+        assert_eq!(
+            check(vec![(0, 0), (0, 2), (0, 3)], 7),
+            Err(PathViolation::NotAdjacent {
+                step: 1,
+                from: (0, 0),
+                to: (0, 2)
+            })
+        );
+        assert_eq!(
+            check(vec![(0, 0), (0, 1), (0, 2), (0, 3)], 10),
+            Err(PathViolation::CostMismatch {
+                reported: 10,
+                actual: 9
+            })
+        );
+    }
+
+    #[test]
+    fn test_violation_messages() {
+        let violation = PathViolation::Blocked {
+            step: 3,
+            node: (2, 5),
+        };
+        assert_eq!(
+            violation.to_string(),
+            "step 3 enters the obstacle at (2, 5)"
+        );
+        assert_eq!(
+            search::path_cost(&corridor(), &[(0, 0), (1, 1)]),
+            usize::MAX
+        );
+    }
+}
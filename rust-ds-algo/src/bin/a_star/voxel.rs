@@ -0,0 +1,273 @@
+use crate::search::SearchSpace;
+use crate::{Map, MapNode};
+use serde::{Deserialize, Serialize};
+use std::convert::TryFrom;
+
+/// (floor, row, col) steps: four planar, two vertical and the four diagonal ones last, so that
+/// they can be left out.
+const DIRECTIONS: [(i32, i32, i32); 10] = [
+    (0, 0, 1),
+    (0, -1, 0),
+    (0, 0, -1),
+    (0, 1, 0),
+    (1, 0, 0),
+    (-1, 0, 0),
+    (0, -1, 1),
+    (0, -1, -1),
+    (0, 1, -1),
+    (0, 1, 1),
+];
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash, Ord, PartialOrd, Serialize, Deserialize)]
+pub struct Voxel {
+    pub floor: i32,
+    pub row: i32,
+    pub col: i32,
+}
+
+impl Voxel {
+    pub fn new(floor: i32, row: i32, col: i32) -> Self {
+        Voxel { floor, row, col }
+    }
+
+    fn offset(self, delta: (i32, i32, i32)) -> Self {
+        Voxel::new(self.floor + delta.0, self.row + delta.1, self.col + delta.2)
+    }
+}
+
+/// A stack of equally sized floors. The agent moves between floors wherever two walkable voxels
+/// sit on top of each other, so stairwells are simply columns left open between the floors.
+#[derive(Clone, Debug)]
+pub struct VoxelMap {
+    floors: Vec<Map>,
+    agent_pos: Voxel,
+    diagonal: bool,
+}
+
+impl VoxelMap {
+    pub fn new(floors: Vec<Map>) -> Self {
+        assert!(!floors.is_empty(), "A voxel map needs at least one floor");
+        assert!(
+            floors
+                .iter()
+                .all(|f| f.width == floors[0].width && f.height == floors[0].height),
+            "All floors must have the same dimensions"
+        );
+        let (row, col) = floors[0].agent_pos;
+        VoxelMap {
+            floors,
+            agent_pos: Voxel::new(0, row, col),
+            diagonal: false,
+        }
+    }
+
+    /// Also allows the four diagonal moves within a floor. Corners can't be cut, both orthogonal
+    /// cells next to a diagonal step have to be walkable.
+    pub fn with_diagonal_moves(mut self, diagonal: bool) -> Self {
+        self.diagonal = diagonal;
+        self
+    }
+
+    pub fn agent_pos(&self) -> Voxel {
+        self.agent_pos
+    }
+
+    pub fn set_agent_pos(&mut self, pos: Voxel) {
+        self.agent_pos = pos;
+    }
+
+    fn floor(&self, node: Voxel) -> Option<&Map> {
+        usize::try_from(node.floor)
+            .ok()
+            .and_then(|floor| self.floors.get(floor))
+    }
+
+    pub fn tile(&self, node: Voxel) -> Option<&MapNode> {
+        self.floor(node).and_then(|f| f.tile((node.row, node.col)))
+    }
+
+    pub fn is_walkable(&self, node: Voxel) -> bool {
+        matches!(self.tile(node), Some(MapNode::Path(_)))
+    }
+
+    pub fn draw(&mut self, node: Voxel, char: &str) {
+        if let Some(floor) = self.floors.get_mut(node.floor as usize) {
+            floor.draw((node.row, node.col), char);
+        }
+    }
+
+    /// Renders one grid per floor, the agent is only shown on the floor it stands on.
+    pub fn render(&self) -> String {
+        self.floors
+            .iter()
+            .enumerate()
+            .map(|(i, floor)| {
+                let mut floor = floor.clone();
+                floor.agent_pos = if i as i32 == self.agent_pos.floor {
+                    (self.agent_pos.row, self.agent_pos.col)
+                } else {
+                    (-1, -1)
+                };
+                format!("Floor {}\n{}", i, floor.render())
+            })
+            .collect::<Vec<_>>()
+            .join("\n\n")
+    }
+}
+
+pub struct VoxelNeighbors<'a> {
+    map: &'a VoxelMap,
+    center: Voxel,
+    direction: usize,
+}
+
+impl<'a> Iterator for VoxelNeighbors<'a> {
+    type Item = Voxel;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let directions = if self.map.diagonal { 10 } else { 6 };
+        while self.direction < directions {
+            let delta = DIRECTIONS[self.direction];
+            self.direction += 1;
+            let neighbor = self.center.offset(delta);
+            let cuts_corner = delta.1 != 0
+                && delta.2 != 0
+                && !(self.map.is_walkable(self.center.offset((0, delta.1, 0)))
+                    && self.map.is_walkable(self.center.offset((0, 0, delta.2))));
+            if self.map.is_walkable(neighbor) && !cuts_corner {
+                return Some(neighbor);
+            }
+        }
+        None
+    }
+}
+
+impl SearchSpace for VoxelMap {
+    type Node = Voxel;
+    type Neighbors<'a> = VoxelNeighbors<'a>;
+
+    fn neighbors(&self, node: Voxel) -> VoxelNeighbors<'_> {
+        VoxelNeighbors {
+            map: self,
+            center: node,
+            direction: 0,
+        }
+    }
+
+    fn cost(&self, _from: Voxel, to: Voxel) -> usize {
+        match self.tile(to) {
+            Some(MapNode::Path(cost)) => *cost as usize,
+            _ => usize::MAX,
+        }
+    }
+
+    /// 3D Manhattan distance, or octile distance within a floor when diagonal moves are allowed.
+    /// A diagonal step enters a single tile, so it costs the same as a straight one.
+    fn heuristic(&self, a: Voxel, b: Voxel) -> usize {
+        let floors = (a.floor - b.floor).unsigned_abs() as usize;
+        let rows = (a.row - b.row).unsigned_abs() as usize;
+        let cols = (a.col - b.col).unsigned_abs() as usize;
+        if self.diagonal {
+            floors + rows.max(cols)
+        } else {
+            floors + rows + cols
+        }
+    }
+
+    fn cheapest_step(&self) -> usize {
+        self.floors
+            .iter()
+            .map(|floor| floor.cheapest_step())
+            .min()
+            .unwrap_or(0)
+    }
+
+    /// Sum of the absolute components of the 3D cross product.
+    fn cross_product(&self, start: Voxel, node: Voxel, goal: Voxel) -> usize {
+        let a = [
+            node.floor - goal.floor,
+            node.row - goal.row,
+            node.col - goal.col,
+        ]
+        .map(i64::from);
+        let b = [
+            start.floor - goal.floor,
+            start.row - goal.row,
+            start.col - goal.col,
+        ]
+        .map(i64::from);
+        (0..3)
+            .map(|i| {
+                (a[(i + 1) % 3] * b[(i + 2) % 3] - a[(i + 2) % 3] * b[(i + 1) % 3]).unsigned_abs()
+            })
+            .sum::<u64>() as usize
+    }
+
+    fn is_passable(&self, node: Voxel) -> bool {
+        self.is_walkable(node)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::search::{self, SearchSpace};
+    use crate::voxel::{Voxel, VoxelMap};
+    use crate::{Algorithm, Map, MapNode};
+
+    /// Floors of `size` x `size` open tiles, separated by solid slabs except for a stairwell at
+    /// the given cell.
+    fn building(size: usize, floors: usize, stairs: (usize, usize)) -> VoxelMap {
+        let mut maps = Vec::new();
+        for f in 0..floors * 2 - 1 {
+            let mut map = Map::new(size, size);
+            for (r, row) in map.grid.iter_mut().enumerate() {
+                for (c, node) in row.iter_mut().enumerate() {
+                    *node = if f % 2 == 0 || (r, c) == stairs {
+                        MapNode::Path(1)
+                    } else {
+                        MapNode::HorizontalObstacle
+                    };
+                }
+            }
+            maps.push(map);
+        }
+        VoxelMap::new(maps)
+    }
+
+    #[test]
+    fn test_neighbors() {
+        let map = building(3, 2, (1, 1));
+        assert_eq!(map.neighbors(Voxel::new(0, 1, 1)).count(), 5);
+        assert_eq!(map.neighbors(Voxel::new(0, 0, 0)).count(), 2);
+
+        let map = map.with_diagonal_moves(true);
+        assert_eq!(map.neighbors(Voxel::new(0, 1, 1)).count(), 9);
+        assert_eq!(map.neighbors(Voxel::new(0, 0, 0)).count(), 3);
+    }
+
+    #[test]
+    fn test_all_algorithms_climb_the_stairs() {
+        let map = building(4, 2, (3, 3));
+        let goal = Voxel::new(2, 0, 0);
+
+        for algorithm in Algorithm::ALL {
+            let res = search::find_path(&map, algorithm, map.agent_pos(), goal).unwrap();
+            assert!(res.path.contains(&Voxel::new(1, 3, 3)));
+            assert_eq!(res.path.last(), Some(&goal));
+            if algorithm != Algorithm::Gbfs {
+                assert_eq!(res.cost_of_path, 14);
+            }
+        }
+
+        let map = map.with_diagonal_moves(true);
+        let res = search::a_star(&map, map.agent_pos(), goal).unwrap();
+        assert_eq!(res.cost_of_path, 8);
+    }
+
+    #[test]
+    fn test_render_one_layer_per_floor() {
+        let text = building(2, 2, (0, 0)).render();
+        assert!(text.contains("Floor 0") && text.contains("Floor 2"));
+        assert_eq!(text.matches('╳').count(), 1);
+    }
+}
@@ -0,0 +1,246 @@
+use crate::search::{self, SearchSpace};
+use crate::{Map, PathfindingResult};
+use std::collections::HashSet;
+
+const MAX_EXAMINED_PER_PATH: usize = 100;
+
+/// A search space with some nodes and steps taken out, used for the spur searches.
+struct Restricted<'a, S: SearchSpace> {
+    space: &'a S,
+    nodes: HashSet<S::Node>,
+    edges: HashSet<(S::Node, S::Node)>,
+}
+
+struct RestrictedNeighbors<'a, S: SearchSpace + 'a> {
+    inner: S::Neighbors<'a>,
+    from: S::Node,
+    restricted: &'a Restricted<'a, S>,
+}
+
+impl<'a, S: SearchSpace> Iterator for RestrictedNeighbors<'a, S> {
+    type Item = S::Node;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let restricted = self.restricted;
+        let from = self.from;
+        self.inner
+            .find(|&to| !restricted.nodes.contains(&to) && !restricted.edges.contains(&(from, to)))
+    }
+}
+
+impl<'a, S: SearchSpace> SearchSpace for Restricted<'a, S> {
+    type Node = S::Node;
+    type Neighbors<'b>
+        = RestrictedNeighbors<'b, S>
+    where
+        Self: 'b;
+
+    fn neighbors(&self, node: S::Node) -> RestrictedNeighbors<'_, S> {
+        RestrictedNeighbors {
+            inner: self.space.neighbors(node),
+            from: node,
+            restricted: self,
+        }
+    }
+
+    fn cost(&self, from: S::Node, to: S::Node) -> usize {
+        self.space.cost(from, to)
+    }
+
+    fn heuristic(&self, a: S::Node, b: S::Node) -> usize {
+        self.space.heuristic(a, b)
+    }
+
+    fn cheapest_step(&self) -> usize {
+        self.space.cheapest_step()
+    }
+
+    fn is_passable(&self, node: S::Node) -> bool {
+        self.space.is_passable(node)
+    }
+}
+
+/// Share of the cells of `path` that also lie on `other`.
+fn overlap<N: Eq + std::hash::Hash>(path: &[N], other: &HashSet<&N>) -> f64 {
+    path.iter().filter(|node| other.contains(node)).count() as f64 / path.len() as f64
+}
+
+/// Yen's algorithm: up to `k` loopless paths from `start` to `goal`, cheapest first, each found
+/// with Dijkstra. `nodes_visited` of a path counts the nodes of the search that found it.
+///
+/// With `max_overlap`, a candidate sharing more than that fraction of its cells with an already
+/// returned path is skipped. Skipped paths still lead to further candidates, since diverse ones
+/// may only turn up much later, but the search gives up after `MAX_EXAMINED_PER_PATH` paths per
+/// requested one.
+pub fn k_shortest_paths<S: SearchSpace>(
+    space: &S,
+    start: S::Node,
+    goal: S::Node,
+    k: usize,
+    max_overlap: Option<f64>,
+) -> Vec<PathfindingResult<S::Node>> {
+    let mut accepted: Vec<PathfindingResult<S::Node>> = Vec::new();
+    let mut examined: Vec<Vec<S::Node>> = Vec::new();
+    let mut candidates: Vec<PathfindingResult<S::Node>> = Vec::new();
+    let mut known = HashSet::new();
+
+    if let Some(first) = search::dijkstra(space, start, goal) {
+        known.insert(first.path.clone());
+        candidates.push(first);
+    }
+
+    while accepted.len() < k && examined.len() < k.saturating_mul(MAX_EXAMINED_PER_PATH) {
+        let best = match candidates
+            .iter()
+            .enumerate()
+            .min_by(|(_, a), (_, b)| (a.cost_of_path, &a.path).cmp(&(b.cost_of_path, &b.path)))
+        {
+            Some((i, _)) => candidates.swap_remove(i),
+            None => break,
+        };
+        examined.push(best.path.clone());
+
+        for i in 0..best.path.len() - 1 {
+            let spur = best.path[i];
+            let root = &best.path[..=i];
+            let mut restricted = Restricted {
+                space,
+                nodes: root[..i].iter().copied().collect(),
+                edges: HashSet::new(),
+            };
+            for other in &examined {
+                if other.len() > i + 1 && other[..=i] == *root {
+                    restricted.edges.insert((spur, other[i + 1]));
+                }
+            }
+
+            if let Some(spur_path) = search::dijkstra(&restricted, spur, goal) {
+                let path = root[..i]
+                    .iter()
+                    .chain(&spur_path.path)
+                    .copied()
+                    .collect::<Vec<_>>();
+                if known.insert(path.clone()) {
+                    candidates.push(PathfindingResult {
+                        cost_of_path: search::path_cost(space, &path),
+                        path,
+                        ..spur_path
+                    });
+                }
+            }
+        }
+
+        let too_similar = max_overlap.is_some_and(|max_overlap| {
+            accepted
+                .iter()
+                .any(|other| overlap(&best.path, &other.path.iter().collect()) > max_overlap)
+        });
+        if !too_similar {
+            accepted.push(best);
+        }
+    }
+
+    if cfg!(debug_assertions) {
+        for (i, res) in accepted.iter().enumerate() {
+            if let Err(violation) = res.validate(space, start, goal) {
+                panic!(
+                    "Path {} of Yen's algorithm is invalid: {}",
+                    i + 1,
+                    violation
+                );
+            }
+        }
+    }
+    accepted
+}
+
+impl Map {
+    pub fn k_shortest_paths(
+        &self,
+        goal: (i32, i32),
+        k: usize,
+        max_overlap: Option<f64>,
+    ) -> Vec<PathfindingResult> {
+        k_shortest_paths(self, self.agent_pos, goal, k, max_overlap)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::search;
+    use crate::{Map, MapNode};
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+    use std::collections::HashSet;
+
+    fn open_map(width: usize, height: usize) -> Map {
+        let mut map = Map::new(width, height);
+        for node in map.grid.iter_mut().flatten() {
+            *node = MapNode::Path(1);
+        }
+        map
+    }
+
+    #[test]
+    fn test_paths_are_loopless_distinct_and_sorted() {
+        let mut map = Map::new(12, 12);
+        map.generate_map(&mut StdRng::seed_from_u64(11), (5, 10));
+        map.agent_pos = (0, 0);
+        map.grid[0][0] = MapNode::Path(1);
+        map.grid[11][11] = MapNode::Path(1);
+        let best = search::dijkstra(&map, (0, 0), (11, 11)).expect("the fixture has a path");
+
+        let paths = map.k_shortest_paths((11, 11), 8, None);
+        assert_eq!(paths.len(), 8);
+        assert_eq!(paths[0].cost_of_path, best.cost_of_path);
+        assert!(paths
+            .windows(2)
+            .all(|w| w[0].cost_of_path <= w[1].cost_of_path));
+        assert_eq!(
+            paths.iter().map(|p| &p.path).collect::<HashSet<_>>().len(),
+            paths.len()
+        );
+        for path in &paths {
+            assert_eq!(
+                path.path.iter().collect::<HashSet<_>>().len(),
+                path.path.len()
+            );
+            assert_eq!(path.validate(&map, (0, 0), (11, 11)), Ok(()));
+        }
+    }
+
+    #[test]
+    fn test_two_corridors() {
+        // Two ways around a block in the middle, the upper one cheaper.
+        let mut map = open_map(5, 3);
+        map.grid[1][1] = MapNode::VerticalObstacle;
+        map.grid[1][2] = MapNode::VerticalObstacle;
+        map.grid[1][3] = MapNode::VerticalObstacle;
+        for col in 0..5 {
+            map.grid[2][col] = MapNode::Path(2);
+        }
+        map.agent_pos = (1, 0);
+
+        let paths = map.k_shortest_paths((1, 4), 5, None);
+        assert_eq!(paths.len(), 2);
+        assert_eq!(paths[0].cost_of_path, 6);
+        assert_eq!(paths[1].cost_of_path, 11);
+    }
+
+    #[test]
+    fn test_diversity_filter() {
+        let map = open_map(6, 6);
+        let all = map.k_shortest_paths((5, 5), 5, None);
+        assert_eq!(all.len(), 5);
+        assert!(all.iter().all(|p| p.cost_of_path == 10));
+
+        let diverse = map.k_shortest_paths((5, 5), 5, Some(0.5));
+        assert!(diverse.len() >= 2);
+        for (i, a) in diverse.iter().enumerate() {
+            for b in &diverse[..i] {
+                let shared = a.path.iter().filter(|node| b.path.contains(node)).count();
+                assert!(shared as f64 / a.path.len() as f64 <= 0.5);
+            }
+        }
+    }
+}
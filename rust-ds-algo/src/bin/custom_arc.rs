@@ -1,11 +1,14 @@
-#![feature(ptr_internals)]
-
+use std::marker::PhantomData;
 use std::ops::Deref;
-use std::ptr::Unique;
+use std::ptr::NonNull;
 use std::sync::atomic::{fence, AtomicUsize, Ordering};
 
+/// `PhantomData` says that a `CustomArc` owns its `InnerArc`, which `NonNull` alone doesn't.
+/// Drop check doesn't rely on it yet: without the unstable `#[may_dangle]`, the `Drop` impl
+/// alone makes it assume a `T` may be used when the last clone is dropped.
 pub struct CustomArc<T> {
-    inner: Unique<InnerArc<T>>,
+    inner: NonNull<InnerArc<T>>,
+    _owns: PhantomData<InnerArc<T>>,
 }
 
 impl<T> CustomArc<T> {
@@ -15,7 +18,8 @@ impl<T> CustomArc<T> {
             data,
         });
         CustomArc {
-            inner: Unique::new(Box::into_raw(boxed)).unwrap(),
+            inner: NonNull::new(Box::into_raw(boxed)).unwrap(),
+            _owns: PhantomData,
         }
     }
 }
@@ -41,12 +45,13 @@ impl<T> Clone for CustomArc<T> {
         let inner = unsafe { &(*self.inner.as_ptr()) };
         let old_rc = inner.count.fetch_add(1, Ordering::Relaxed);
 
-        if old_rc as isize > isize::MAX {
+        if old_rc >= isize::MAX as usize {
             std::process::abort();
         }
 
         CustomArc {
-            inner: self.inner.clone(),
+            inner: self.inner,
+            _owns: PhantomData,
         }
     }
 }
@@ -61,8 +66,61 @@ impl<T> Drop for CustomArc<T> {
 
         fence(Ordering::Acquire);
 
-        unsafe { Box::from_raw(self.inner.as_ptr()) };
+        drop(unsafe { Box::from_raw(self.inner.as_ptr()) });
+    }
+}
+
+fn main() {
+    let arc = CustomArc::new(vec![1, 2, 3]);
+    let handles = (0..3)
+        .map(|i| {
+            let arc = arc.clone();
+            std::thread::spawn(move || arc[i] * 2)
+        })
+        .collect::<Vec<_>>();
+    for handle in handles {
+        println!("{}", handle.join().unwrap());
     }
 }
 
-fn main() {}
+#[cfg(test)]
+mod tests {
+    use crate::CustomArc;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    struct Inspector<'a>(&'a AtomicUsize);
+
+    impl<'a> Drop for Inspector<'a> {
+        fn drop(&mut self) {
+            self.0.fetch_add(1, Ordering::SeqCst);
+        }
+    }
+
+    #[test]
+    fn test_last_clone_drops_the_data() {
+        // As with `CustomVec`, declaring the counter after the arc wouldn't compile because of
+        // the `Drop` impl, which a binary can't test with a `compile_fail` doctest.
+        let drops = AtomicUsize::new(0);
+        let arc = CustomArc::new(Inspector(&drops));
+        std::thread::scope(|scope| {
+            for _ in 0..4 {
+                let arc = arc.clone();
+                scope.spawn(move || drop(arc));
+            }
+        });
+        assert_eq!(drops.load(Ordering::SeqCst), 0);
+        drop(arc);
+        assert_eq!(drops.load(Ordering::SeqCst), 1);
+    }
+
+    #[test]
+    fn test_variance_and_auto_traits() {
+        fn shorten<'a>(arc: CustomArc<&'static str>) -> CustomArc<&'a str> {
+            arc
+        }
+        fn assert_send_sync<T: Send + Sync>() {}
+
+        assert_eq!(*shorten(CustomArc::new("static")), "static");
+        assert_send_sync::<CustomArc<String>>();
+    }
+}
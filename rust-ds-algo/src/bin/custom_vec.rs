@@ -1,30 +1,138 @@
-#![feature(ptr_internals)]
-
 use std::alloc::handle_alloc_error;
 use std::alloc::realloc;
 use std::alloc::Layout;
 use std::alloc::{alloc, dealloc};
+use std::cmp::Ordering;
+use std::fmt::{Debug, Display, Formatter};
+use std::hash::{Hash, Hasher};
+use std::iter::FromIterator;
 use std::marker::PhantomData;
-use std::ops::{Deref, DerefMut};
-use std::ptr::Unique;
+use std::mem::ManuallyDrop;
+use std::ops::{Bound, Deref, DerefMut, Index, IndexMut, RangeBounds};
+use std::ptr::NonNull;
+use std::slice::SliceIndex;
 
-struct Drain<'a, T> {
-    data: PhantomData<&'a mut Vec<T>>,
-    raw_iter: RawIter<T>,
+/// Builds a `CustomVec` like `vec!` builds a `Vec`: from a list of elements, or from one
+/// element cloned as many times as asked for.
+macro_rules! custom_vec {
+    () => {
+        $crate::CustomVec::new()
+    };
+    ($elem:expr; $n:expr) => {
+        ::std::iter::repeat($elem)
+            .take($n)
+            .collect::<$crate::CustomVec<_>>()
+    };
+    ($($x:expr),+ $(,)?) => {
+        $crate::CustomVec::from([$($x),+])
+    };
 }
 
-impl<'a, T> Drain<'a, T> {
-    pub fn new(raw_iter: RawIter<T>) -> Self {
-        Drain {
-            data: PhantomData::default(),
-            raw_iter,
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub enum TryReserveError {
+    /// The capacity asked for doesn't fit in a `usize`, or its size in bytes in an `isize`.
+    CapacityOverflow,
+    /// The allocator couldn't provide `layout`.
+    AllocError { layout: Layout },
+}
+
+impl Display for TryReserveError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            TryReserveError::CapacityOverflow => write!(f, "capacity overflow"),
+            TryReserveError::AllocError { layout } => {
+                write!(f, "could not allocate {} bytes", layout.size())
+            }
         }
     }
 }
 
+impl std::error::Error for TryReserveError {}
+
+/// An index past the end of a `CustomVec` of `len` elements.
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub struct OutOfBounds {
+    pub index: usize,
+    pub len: usize,
+}
+
+impl Display for OutOfBounds {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "index {} out of bounds for length {}",
+            self.index, self.len
+        )
+    }
+}
+
+impl std::error::Error for OutOfBounds {}
+
+/// Bounds of `range` within a slice of `len` elements, panicking like slice indexing does.
+fn slice_range(range: impl RangeBounds<usize>, len: usize) -> (usize, usize) {
+    let start = match range.start_bound() {
+        Bound::Included(&start) => start,
+        Bound::Excluded(&start) => start
+            .checked_add(1)
+            .expect("attempted to index slice from after maximum usize"),
+        Bound::Unbounded => 0,
+    };
+    let end = match range.end_bound() {
+        Bound::Included(&end) => end
+            .checked_add(1)
+            .expect("attempted to index slice up to maximum usize"),
+        Bound::Excluded(&end) => end,
+        Bound::Unbounded => len,
+    };
+    assert!(
+        start <= end,
+        "slice index starts at {} but ends at {}",
+        start,
+        end
+    );
+    assert!(
+        end <= len,
+        "range end index {} out of range for slice of length {}",
+        end,
+        len
+    );
+    (start, end)
+}
+
+/// The elements removed by `CustomVec::drain`. The vector is cut short before the range while
+/// draining, so leaking the drain leaks the elements after it but never drops any twice. They
+/// are moved back when the drain is dropped.
+struct Drain<'a, T> {
+    vec: NonNull<CustomVec<T>>,
+    raw_iter: RawIter<T>,
+    tail_start: usize,
+    tail_len: usize,
+    data: PhantomData<&'a mut CustomVec<T>>,
+}
+
 impl<'a, T> Drop for Drain<'a, T> {
     fn drop(&mut self) {
-        for _ in &mut *self {}
+        /// Moves the tail back even if dropping an element panics.
+        struct MoveTail<'r, 'a, T>(&'r mut Drain<'a, T>);
+
+        impl<'r, 'a, T> Drop for MoveTail<'r, 'a, T> {
+            fn drop(&mut self) {
+                let drain = &mut *self.0;
+                unsafe {
+                    let vec = drain.vec.as_mut();
+                    let data = vec.raw.data.as_ptr();
+                    std::ptr::copy(
+                        data.add(drain.tail_start),
+                        data.add(vec.len),
+                        drain.tail_len,
+                    );
+                    vec.len += drain.tail_len;
+                }
+            }
+        }
+
+        let guard = MoveTail(self);
+        unsafe { guard.0.raw_iter.drop_remaining() }
     }
 }
 
@@ -46,20 +154,79 @@ impl<'a, T> DoubleEndedIterator for Drain<'a, T> {
     }
 }
 
+/// The elements removed by `CustomVec::splice`. The replacements are only inserted, and
+/// `replace_with` only consumed, when the splice is dropped.
+struct Splice<'a, I: Iterator> {
+    drain: Drain<'a, I::Item>,
+    replace_with: I,
+}
+
+impl<'a, I: Iterator> Iterator for Splice<'a, I> {
+    type Item = I::Item;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.drain.next()
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.drain.size_hint()
+    }
+}
+
+impl<'a, I: Iterator> DoubleEndedIterator for Splice<'a, I> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        self.drain.next_back()
+    }
+}
+
+impl<'a, I: Iterator> Drop for Splice<'a, I> {
+    fn drop(&mut self) {
+        for _ in &mut self.drain {}
+        // Until the tail has moved out of their way, the replacements have to wait elsewhere.
+        let replacements = (&mut self.replace_with).collect::<Vec<_>>();
+        let drain = &mut self.drain;
+        unsafe {
+            // Making room may move the buffer, after which the spent iterator would point into
+            // freed memory once the drain drops what it thinks is left.
+            drain.raw_iter = RawIter::new(NonNull::dangling().as_ptr(), 0);
+            let vec = drain.vec.as_mut();
+            let start = vec.len;
+            vec.raw
+                .reserve(start + drain.tail_len, replacements.len(), false);
+            let data = vec.raw.data.as_ptr();
+            std::ptr::copy(
+                data.add(drain.tail_start),
+                data.add(start + replacements.len()),
+                drain.tail_len,
+            );
+            drain.tail_start = start + replacements.len();
+            for (i, val) in replacements.into_iter().enumerate() {
+                std::ptr::write(data.add(start + i), val);
+                vec.len += 1;
+            }
+        }
+    }
+}
+
+/// Walks the elements by index rather than by pointer, as every element of a zero-sized type
+/// has the same address and pointer differences can't count them.
 struct RawIter<T> {
-    start: *const T,
-    end: *const T,
+    data: *mut T,
+    start: usize,
+    end: usize,
 }
 
 impl<T> RawIter<T> {
-    pub fn new(raw_arr: &[T]) -> Self {
+    /// # Safety
+    ///
+    /// `data` must point to `len` valid elements that the iterator takes over, and be derived
+    /// from the owning pointer of the array rather than from a borrowed slice, as the elements
+    /// left over are dropped through it.
+    pub unsafe fn new(data: *mut T, len: usize) -> Self {
         RawIter {
-            start: raw_arr.as_ptr(),
-            end: if raw_arr.len() == 0 {
-                raw_arr.as_ptr()
-            } else {
-                unsafe { raw_arr.as_ptr().add(raw_arr.len()) }
-            },
+            data,
+            start: 0,
+            end: len,
         }
     }
 
@@ -68,15 +235,15 @@ impl<T> RawIter<T> {
             None
         } else {
             unsafe {
-                let val = Some(std::ptr::read(self.start));
-                self.start = self.start.add(1);
+                let val = Some(std::ptr::read(self.data.add(self.start)));
+                self.start += 1;
                 val
             }
         }
     }
 
     fn size_hint(&self) -> (usize, Option<usize>) {
-        let len = unsafe { self.end.offset_from(self.start) } as usize;
+        let len = self.end - self.start;
 
         (len, Some(len))
     }
@@ -86,68 +253,166 @@ impl<T> RawIter<T> {
             None
         } else {
             unsafe {
-                let val = Some(std::ptr::read(self.end.offset(-1)));
-                self.end = self.end.offset(-1);
-                val
+                self.end -= 1;
+                Some(std::ptr::read(self.data.add(self.end)))
             }
         }
     }
+
+    /// Drops the elements not read yet in place, like `Vec`'s iterators. They are marked as read
+    /// first, and dropping a slice keeps going past an element that panics, so each one is
+    /// dropped exactly once either way.
+    ///
+    /// # Safety
+    ///
+    /// The remaining elements must be valid and not read through any other pointer.
+    unsafe fn drop_remaining(&mut self) {
+        let remaining =
+            std::ptr::slice_from_raw_parts_mut(self.data.add(self.start), self.end - self.start);
+        self.start = self.end;
+        std::ptr::drop_in_place(remaining);
+    }
 }
 
+/// `NonNull` is covariant in `T` like `Unique` was, but doesn't say that the array owns its
+/// elements. `PhantomData<T>` does. Drop check doesn't rely on it yet: without the unstable
+/// `#[may_dangle]`, the `Drop` impl alone makes it assume a `T` may be used when the array is
+/// dropped.
 struct RawDynamicArray<T> {
-    data: Unique<T>,
+    data: NonNull<T>,
     cap: usize,
+    _owns: PhantomData<T>,
 }
 
+// The array owns its elements and hands out references to them only through `&self` and
+// `&mut self`, so it can be sent and shared whenever they can.
+unsafe impl<T: Send> Send for RawDynamicArray<T> {}
+unsafe impl<T: Sync> Sync for RawDynamicArray<T> {}
+
 impl<T> RawDynamicArray<T> {
+    const IS_ZST: bool = std::mem::size_of::<T>() == 0;
+
+    /// Zero-sized types never need memory, the dangling pointer holds as many of them as fit in
+    /// a `usize`.
     pub fn new() -> Self {
         RawDynamicArray {
-            data: Unique::dangling(),
-            cap: 0,
+            data: NonNull::dangling(),
+            cap: if Self::IS_ZST { usize::MAX } else { 0 },
+            _owns: PhantomData,
         }
     }
 
-    fn grow(&mut self) {
-        let (new_cap, new_layout) = if self.cap == 0 {
-            (1, Layout::array::<T>(1).unwrap())
+    pub fn with_capacity(cap: usize) -> Self {
+        let mut raw = RawDynamicArray::new();
+        raw.reserve(0, cap, false);
+        raw
+    }
+
+    /// Makes room for `additional` elements after the first `len`, doubling the capacity at
+    /// least unless `exact` is set. Panics on overflow and aborts when out of memory, like `Vec`.
+    fn reserve(&mut self, len: usize, additional: usize, exact: bool) {
+        match self.try_reserve(len, additional, exact) {
+            Ok(()) => {}
+            Err(TryReserveError::CapacityOverflow) => panic!("Capacity overflow"),
+            Err(TryReserveError::AllocError { layout }) => handle_alloc_error(layout),
+        }
+    }
+
+    fn try_reserve(
+        &mut self,
+        len: usize,
+        additional: usize,
+        exact: bool,
+    ) -> Result<(), TryReserveError> {
+        let required = len
+            .checked_add(additional)
+            .ok_or(TryReserveError::CapacityOverflow)?;
+        // The capacity of zero-sized types only runs out when the length overflows.
+        if required <= self.cap {
+            return Ok(());
+        }
+        let new_cap = if exact {
+            required
         } else {
-            let new_cap = 2 * self.cap;
-            (new_cap, Layout::array::<T>(new_cap).unwrap())
+            Self::grown_capacity(self.cap, required)
         };
+        self.resize(new_cap)
+    }
 
-        assert!(
-            new_layout.size() <= isize::MAX as usize,
-            "Allocation too large"
-        );
+    /// At least double `cap`, unless that is more than an array can hold. Then settle for
+    /// `required`, which may still fit.
+    fn grown_capacity(cap: usize, required: usize) -> usize {
+        let doubled = required.max(cap.saturating_mul(2));
+        if Layout::array::<T>(doubled).is_ok() {
+            doubled
+        } else {
+            required
+        }
+    }
+
+    fn grow(&mut self) {
+        self.reserve(self.cap, 1, false);
+    }
+
+    fn shrink_to(&mut self, cap: usize) {
+        if Self::IS_ZST || cap >= self.cap {
+            return;
+        }
+        if let Err(TryReserveError::AllocError { layout }) = self.resize(cap) {
+            handle_alloc_error(layout);
+        }
+    }
 
-        let new_data_ptr = if self.cap == 0 {
+    /// Moves the elements to an allocation for exactly `new_cap` of them, or frees it for none.
+    /// The array is left as it was on errors.
+    fn resize(&mut self, new_cap: usize) -> Result<(), TryReserveError> {
+        let new_layout =
+            Layout::array::<T>(new_cap).map_err(|_| TryReserveError::CapacityOverflow)?;
+
+        let new_data_ptr = if new_cap == 0 {
+            unsafe { dealloc(self.data.as_ptr() as *mut u8, self.layout()) };
+            NonNull::dangling().as_ptr()
+        } else if self.cap == 0 {
             unsafe { alloc(new_layout) }
         } else {
-            let old_layout = Layout::array::<T>(self.cap).unwrap();
-            unsafe { realloc(self.data.as_ptr() as *mut u8, old_layout, new_layout.size()) }
+            unsafe {
+                realloc(
+                    self.data.as_ptr() as *mut u8,
+                    self.layout(),
+                    new_layout.size(),
+                )
+            }
         };
 
-        self.data = if let Some(p) = Unique::new(new_data_ptr as *mut T) {
-            p
-        } else {
-            handle_alloc_error(new_layout);
+        self.data = match NonNull::new(new_data_ptr as *mut T) {
+            Some(p) => p,
+            None => return Err(TryReserveError::AllocError { layout: new_layout }),
         };
 
         self.cap = new_cap;
+        Ok(())
+    }
+
+    fn layout(&self) -> Layout {
+        Layout::array::<T>(self.cap).unwrap()
     }
 }
 
 impl<T> Drop for RawDynamicArray<T> {
     fn drop(&mut self) {
-        let layout = Layout::array::<T>(self.cap).unwrap();
+        // Nothing was allocated for an empty array, or one of zero-sized types.
+        if Self::IS_ZST || self.cap == 0 {
+            return;
+        }
         unsafe {
-            dealloc(self.data.as_ptr() as *mut u8, layout);
+            dealloc(self.data.as_ptr() as *mut u8, self.layout());
         }
     }
 }
 
 struct IntoIter<T> {
-    data: RawDynamicArray<T>,
+    /// Only kept to free the memory once the elements are gone.
+    _data: RawDynamicArray<T>,
     iter: RawIter<T>,
 }
 
@@ -165,15 +430,14 @@ impl<T> Iterator for IntoIter<T> {
 
 impl<T> DoubleEndedIterator for IntoIter<T> {
     fn next_back(&mut self) -> Option<Self::Item> {
-        self.next_back()
+        self.iter.next_back()
     }
 }
 
 impl<T> Drop for IntoIter<T> {
     fn drop(&mut self) {
-        if self.data.cap != 0 {
-            for _ in &mut *self {}
-        }
+        // `_data` frees the memory afterwards, even if an element panics.
+        unsafe { self.iter.drop_remaining() }
     }
 }
 
@@ -190,6 +454,37 @@ impl<T> CustomVec<T> {
         }
     }
 
+    pub fn with_capacity(cap: usize) -> Self {
+        CustomVec {
+            raw: RawDynamicArray::with_capacity(cap),
+            len: 0,
+        }
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.raw.cap
+    }
+
+    /// Makes room for at least `additional` more elements, growing like `push` does.
+    pub fn reserve(&mut self, additional: usize) {
+        self.raw.reserve(self.len, additional, false);
+    }
+
+    /// Makes room for exactly `additional` more elements, unless there already is.
+    pub fn reserve_exact(&mut self, additional: usize) {
+        self.raw.reserve(self.len, additional, true);
+    }
+
+    /// `reserve`, but returning an error instead of panicking or aborting. The vector is left
+    /// as it was on errors.
+    pub fn try_reserve(&mut self, additional: usize) -> Result<(), TryReserveError> {
+        self.raw.try_reserve(self.len, additional, false)
+    }
+
+    pub fn shrink_to_fit(&mut self) {
+        self.raw.shrink_to(self.len);
+    }
+
     pub fn push(&mut self, val: T) {
         if self.len == self.raw.cap {
             self.raw.grow();
@@ -202,6 +497,15 @@ impl<T> CustomVec<T> {
         self.len += 1;
     }
 
+    /// `push`, but handing `val` back with the error if there is no room for it.
+    pub fn try_push(&mut self, val: T) -> Result<(), (T, TryReserveError)> {
+        if let Err(e) = self.try_reserve(1) {
+            return Err((val, e));
+        }
+        self.push(val);
+        Ok(())
+    }
+
     pub fn pop(&mut self) -> Option<T> {
         if self.len == 0 {
             return None;
@@ -213,42 +517,66 @@ impl<T> CustomVec<T> {
         Some(val)
     }
 
-    pub fn insert(&mut self, val: T, i: usize) {
+    /// Inserts `element` at `index`, shifting the elements after it. Panics if `index` is past
+    /// the end.
+    pub fn insert(&mut self, index: usize, element: T) {
+        if let Err((_, e)) = self.try_insert(index, element) {
+            panic!(
+                "insertion index (is {}) should be <= len (is {})",
+                e.index, e.len
+            );
+        }
+    }
+
+    /// `insert`, but handing `element` back with the error if `index` is past the end.
+    pub fn try_insert(&mut self, index: usize, element: T) -> Result<(), (T, OutOfBounds)> {
+        if index > self.len {
+            return Err((
+                element,
+                OutOfBounds {
+                    index,
+                    len: self.len,
+                },
+            ));
+        }
         if self.len == self.raw.cap {
             self.raw.grow();
         }
 
         unsafe {
             std::ptr::copy(
-                self.raw.data.as_ptr().add(i),
-                self.raw.data.as_ptr().add(i + 1),
-                self.len - i,
+                self.raw.data.as_ptr().add(index),
+                self.raw.data.as_ptr().add(index + 1),
+                self.len - index,
             );
-            std::ptr::write(self.raw.data.as_ptr().add(i), val);
+            std::ptr::write(self.raw.data.as_ptr().add(index), element);
         }
 
         self.len += 1;
+        Ok(())
     }
 
-    pub fn into_iter(self) -> IntoIter<T> {
-        let len = self.len;
-        let data = unsafe { std::ptr::read(&self.raw) };
-        let raw_iter = RawIter::new(&self);
-
-        std::mem::forget(self);
-
-        IntoIter {
-            data,
-            iter: raw_iter,
+    /// Removes the element at index `i`, shifting the elements after it. Panics if `i` is out of
+    /// bounds.
+    pub fn remove(&mut self, i: usize) -> T {
+        match self.try_remove(i) {
+            Ok(val) => val,
+            Err(e) => panic!(
+                "removal index (is {}) should be < len (is {})",
+                e.index, e.len
+            ),
         }
     }
 
-    pub fn remove(&mut self, i: usize) -> Option<T> {
-        if self.len == 0 {
-            return None;
+    pub fn try_remove(&mut self, i: usize) -> Result<T, OutOfBounds> {
+        if i >= self.len {
+            return Err(OutOfBounds {
+                index: i,
+                len: self.len,
+            });
         }
 
-        Some(unsafe {
+        Ok(unsafe {
             self.len -= 1;
             let val = std::ptr::read(self.raw.data.as_ptr().add(i));
             std::ptr::copy(
@@ -260,8 +588,180 @@ impl<T> CustomVec<T> {
         })
     }
 
-    pub fn drain(&mut self) -> Drain<T> {
-        Drain::new(RawIter::new(&self))
+    /// Removes the elements in `range`, yielding them, and closes the gap once the drain is
+    /// dropped. Panics if the range is out of bounds.
+    pub fn drain(&mut self, range: impl RangeBounds<usize>) -> Drain<'_, T> {
+        let (start, end) = slice_range(range, self.len);
+        let raw_iter = unsafe { RawIter::new(self.raw.data.as_ptr().add(start), end - start) };
+        let tail_len = self.len - end;
+        // The drain owns the range now, whether it yields the elements or drops them.
+        self.len = start;
+        Drain {
+            vec: NonNull::from(self),
+            raw_iter,
+            tail_start: end,
+            tail_len,
+            data: PhantomData,
+        }
+    }
+
+    /// Replaces the elements in `range` with those of `replace_with`, yielding the removed
+    /// ones like `drain` does. Panics if the range is out of bounds.
+    pub fn splice<I: IntoIterator<Item = T>>(
+        &mut self,
+        range: impl RangeBounds<usize>,
+        replace_with: I,
+    ) -> Splice<'_, I::IntoIter> {
+        Splice {
+            drain: self.drain(range),
+            replace_with: replace_with.into_iter(),
+        }
+    }
+
+    pub fn retain(&mut self, mut f: impl FnMut(&T) -> bool) {
+        self.retain_mut(|val| f(val));
+    }
+
+    /// Keeps only the elements `f` returns true for, in order. If `f` or dropping an element
+    /// panics, the elements not looked at yet are kept.
+    pub fn retain_mut(&mut self, mut f: impl FnMut(&mut T) -> bool) {
+        let len = self.len;
+        let mut compact = Compact {
+            vec: self,
+            read: 0,
+            write: 0,
+            len,
+        };
+        // Nothing is left for the vector to drop twice if this panics before the guard fixes it.
+        compact.vec.len = 0;
+
+        while compact.read < len {
+            unsafe {
+                let data = compact.vec.raw.data.as_ptr();
+                let cur = data.add(compact.read);
+                if f(&mut *cur) {
+                    std::ptr::copy(cur, data.add(compact.write), 1);
+                    compact.read += 1;
+                    compact.write += 1;
+                } else {
+                    compact.read += 1;
+                    std::ptr::drop_in_place(cur);
+                }
+            }
+        }
+    }
+
+    /// Removes all but the first of consecutive elements that `same_bucket` says are the same,
+    /// called with each element and the last one kept before it.
+    pub fn dedup_by(&mut self, mut same_bucket: impl FnMut(&mut T, &mut T) -> bool) {
+        let len = self.len;
+        if len <= 1 {
+            return;
+        }
+        let mut compact = Compact {
+            vec: self,
+            read: 1,
+            write: 1,
+            len,
+        };
+        compact.vec.len = 0;
+
+        while compact.read < len {
+            unsafe {
+                let data = compact.vec.raw.data.as_ptr();
+                let cur = data.add(compact.read);
+                if same_bucket(&mut *cur, &mut *data.add(compact.write - 1)) {
+                    compact.read += 1;
+                    std::ptr::drop_in_place(cur);
+                } else {
+                    std::ptr::copy(cur, data.add(compact.write), 1);
+                    compact.read += 1;
+                    compact.write += 1;
+                }
+            }
+        }
+    }
+
+    /// Removes all but the first of consecutive elements with the same key.
+    pub fn dedup_by_key<K: PartialEq>(&mut self, mut key: impl FnMut(&mut T) -> K) {
+        self.dedup_by(|a, b| key(a) == key(b));
+    }
+
+    /// Moves the elements from `at` on into a new vector. Panics if `at` is past the end.
+    pub fn split_off(&mut self, at: usize) -> Self {
+        assert!(
+            at <= self.len,
+            "`at` split index (is {}) should be <= len (is {})",
+            at,
+            self.len
+        );
+        let mut other = CustomVec::with_capacity(self.len - at);
+        unsafe {
+            std::ptr::copy_nonoverlapping(
+                self.raw.data.as_ptr().add(at),
+                other.raw.data.as_ptr(),
+                self.len - at,
+            );
+        }
+        other.len = self.len - at;
+        self.len = at;
+        other
+    }
+
+    /// Moves all elements of `other` to the end, leaving it empty.
+    pub fn append(&mut self, other: &mut Self) {
+        self.reserve(other.len);
+        unsafe {
+            std::ptr::copy_nonoverlapping(other.raw.data.as_ptr(), self.next_ptr(), other.len);
+        }
+        self.len += other.len;
+        other.len = 0;
+    }
+
+    /// Drops the elements from `len` on, if there are any.
+    pub fn truncate(&mut self, len: usize) {
+        if len >= self.len {
+            return;
+        }
+        let tail = std::ptr::slice_from_raw_parts_mut(
+            unsafe { self.raw.data.as_ptr().add(len) },
+            self.len - len,
+        );
+        // Cut short first, so that nothing is dropped twice if a drop panics. `drop_in_place`
+        // still drops the rest of the tail then.
+        self.len = len;
+        unsafe { std::ptr::drop_in_place(tail) };
+    }
+
+    /// Clones the elements of `other` onto the end. If a clone panics, those cloned before it
+    /// stay.
+    pub fn extend_from_slice(&mut self, other: &[T])
+    where
+        T: Clone,
+    {
+        self.reserve(other.len());
+        for val in other {
+            unsafe { std::ptr::write(self.next_ptr(), val.clone()) };
+            self.len += 1;
+        }
+    }
+
+    /// Removes the element at `index`, replacing it with the last one. Panics if `index` is out
+    /// of bounds.
+    pub fn swap_remove(&mut self, index: usize) -> T {
+        assert!(
+            index < self.len,
+            "swap_remove index (is {}) should be < len (is {})",
+            index,
+            self.len
+        );
+        unsafe {
+            let data = self.raw.data.as_ptr();
+            let val = std::ptr::read(data.add(index));
+            self.len -= 1;
+            std::ptr::copy(data.add(self.len), data.add(index), 1);
+            val
+        }
     }
 
     #[inline]
@@ -270,9 +770,33 @@ impl<T> CustomVec<T> {
     }
 }
 
+/// Closes the gap between the elements `retain_mut` and `dedup_by` kept, those before `write`,
+/// and the ones they haven't looked at yet, from `read` up to `len`, once done or on panic.
+struct Compact<'a, T> {
+    vec: &'a mut CustomVec<T>,
+    read: usize,
+    write: usize,
+    len: usize,
+}
+
+impl<'a, T> Drop for Compact<'a, T> {
+    fn drop(&mut self) {
+        unsafe {
+            let data = self.vec.raw.data.as_ptr();
+            std::ptr::copy(
+                data.add(self.read),
+                data.add(self.write),
+                self.len - self.read,
+            );
+        }
+        self.vec.len = self.write + self.len - self.read;
+    }
+}
+
 impl<T> Drop for CustomVec<T> {
     fn drop(&mut self) {
-        while let Some(_) = self.pop() {}
+        // Unlike popping them one by one, this carries on with the rest if a drop panics.
+        self.truncate(0);
     }
 }
 
@@ -290,4 +814,812 @@ impl<T> DerefMut for CustomVec<T> {
     }
 }
 
-fn main() {}
+impl<T, I: SliceIndex<[T]>> Index<I> for CustomVec<T> {
+    type Output = I::Output;
+
+    fn index(&self, index: I) -> &Self::Output {
+        Index::index(&**self, index)
+    }
+}
+
+impl<T, I: SliceIndex<[T]>> IndexMut<I> for CustomVec<T> {
+    fn index_mut(&mut self, index: I) -> &mut Self::Output {
+        IndexMut::index_mut(&mut **self, index)
+    }
+}
+
+impl<T> IntoIterator for CustomVec<T> {
+    type Item = T;
+    type IntoIter = IntoIter<T>;
+
+    fn into_iter(self) -> IntoIter<T> {
+        let data = unsafe { std::ptr::read(&self.raw) };
+        let raw_iter = unsafe { RawIter::new(self.raw.data.as_ptr(), self.len) };
+
+        std::mem::forget(self);
+
+        IntoIter {
+            _data: data,
+            iter: raw_iter,
+        }
+    }
+}
+
+impl<'a, T> IntoIterator for &'a CustomVec<T> {
+    type Item = &'a T;
+    type IntoIter = std::slice::Iter<'a, T>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+impl<'a, T> IntoIterator for &'a mut CustomVec<T> {
+    type Item = &'a mut T;
+    type IntoIter = std::slice::IterMut<'a, T>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter_mut()
+    }
+}
+
+impl<T> Extend<T> for CustomVec<T> {
+    fn extend<I: IntoIterator<Item = T>>(&mut self, iter: I) {
+        let iter = iter.into_iter();
+        self.reserve(iter.size_hint().0);
+        for val in iter {
+            self.push(val);
+        }
+    }
+}
+
+impl<'a, T: Copy + 'a> Extend<&'a T> for CustomVec<T> {
+    fn extend<I: IntoIterator<Item = &'a T>>(&mut self, iter: I) {
+        self.extend(iter.into_iter().copied());
+    }
+}
+
+impl<T> FromIterator<T> for CustomVec<T> {
+    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
+        let mut vec = CustomVec::new();
+        vec.extend(iter);
+        vec
+    }
+}
+
+impl<T> Default for CustomVec<T> {
+    fn default() -> Self {
+        CustomVec::new()
+    }
+}
+
+impl<T: Clone> Clone for CustomVec<T> {
+    fn clone(&self) -> Self {
+        let mut vec = CustomVec::with_capacity(self.len);
+        vec.extend_from_slice(self);
+        vec
+    }
+}
+
+impl<T: Debug> Debug for CustomVec<T> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        Debug::fmt(&**self, f)
+    }
+}
+
+impl<T: PartialEq<U>, U> PartialEq<CustomVec<U>> for CustomVec<T> {
+    fn eq(&self, other: &CustomVec<U>) -> bool {
+        self[..] == other[..]
+    }
+}
+
+impl<T: PartialEq<U>, U> PartialEq<[U]> for CustomVec<T> {
+    fn eq(&self, other: &[U]) -> bool {
+        self[..] == other[..]
+    }
+}
+
+impl<T: PartialEq<U>, U> PartialEq<&[U]> for CustomVec<T> {
+    fn eq(&self, other: &&[U]) -> bool {
+        self[..] == other[..]
+    }
+}
+
+impl<T: PartialEq<U>, U, const N: usize> PartialEq<[U; N]> for CustomVec<T> {
+    fn eq(&self, other: &[U; N]) -> bool {
+        self[..] == other[..]
+    }
+}
+
+impl<T: PartialEq<U>, U> PartialEq<Vec<U>> for CustomVec<T> {
+    fn eq(&self, other: &Vec<U>) -> bool {
+        self[..] == other[..]
+    }
+}
+
+impl<T: Eq> Eq for CustomVec<T> {}
+
+impl<T: PartialOrd> PartialOrd for CustomVec<T> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        PartialOrd::partial_cmp(&**self, &**other)
+    }
+}
+
+impl<T: Ord> Ord for CustomVec<T> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        Ord::cmp(&**self, &**other)
+    }
+}
+
+impl<T: Hash> Hash for CustomVec<T> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        Hash::hash(&**self, state)
+    }
+}
+
+/// Takes over the allocation of `vec` rather than copying it. Both allocate with the global
+/// allocator and `Layout::array::<T>(cap)`, zero-sized types included.
+impl<T> From<Vec<T>> for CustomVec<T> {
+    fn from(vec: Vec<T>) -> Self {
+        let mut vec = ManuallyDrop::new(vec);
+        CustomVec {
+            raw: RawDynamicArray {
+                data: NonNull::new(vec.as_mut_ptr()).unwrap(),
+                cap: vec.capacity(),
+                _owns: PhantomData,
+            },
+            len: vec.len(),
+        }
+    }
+}
+
+/// Hands the allocation over to a `Vec`, see `From<Vec<T>>`.
+impl<T> From<CustomVec<T>> for Vec<T> {
+    fn from(vec: CustomVec<T>) -> Self {
+        let vec = ManuallyDrop::new(vec);
+        let raw = unsafe { std::ptr::read(&vec.raw) };
+        let raw = ManuallyDrop::new(raw);
+        unsafe { Vec::from_raw_parts(raw.data.as_ptr(), vec.len, raw.cap) }
+    }
+}
+
+impl<T, const N: usize> From<[T; N]> for CustomVec<T> {
+    fn from(array: [T; N]) -> Self {
+        IntoIterator::into_iter(array).collect()
+    }
+}
+
+fn main() {
+    let mut vec = CustomVec::new();
+    for i in 0..5 {
+        vec.push(i);
+    }
+    vec.insert(2, 10);
+    println!("{:?}", vec);
+    println!("{:?} {:?}", vec.remove(0), vec.pop());
+    if let Err(e) = vec.try_remove(10) {
+        println!("{}", e);
+    }
+    println!("{:?}", vec.splice(1..3, [7, 8, 9]).collect::<Vec<_>>());
+    vec.retain(|&i| i != 8);
+    vec.retain_mut(|i| {
+        *i *= 2;
+        true
+    });
+    vec.dedup_by_key(|i| *i / 4);
+    vec.extend_from_slice(&[5, 6]);
+    let mut tail = vec.split_off(2);
+    tail.truncate(1);
+    vec.append(&mut tail);
+    println!("{} {:?}", vec.swap_remove(0), &vec[..]);
+    println!("{:?}", vec.drain(..).rev().collect::<Vec<_>>());
+
+    let mut vec = CustomVec::with_capacity(2);
+    vec.push("owned".to_string());
+    vec.push("elements".to_string());
+    vec.reserve(1);
+    vec.reserve_exact(4);
+    println!("{}", vec.capacity());
+    vec.shrink_to_fit();
+    if let Err((val, e)) = vec.try_push("more".to_string()) {
+        println!("{} - {}", val, e);
+    }
+    if let Err(e) = vec.try_reserve(usize::MAX) {
+        println!("{}", e);
+    }
+    println!("{:?}", vec.into_iter().collect::<Vec<_>>());
+
+    let words = custom_vec!["drop", "in", "replacement"];
+    let mut sorted = words.clone();
+    sorted.sort_unstable();
+    println!("{:?} {:?} {}", words, &sorted[1..], words < sorted);
+    let mut vec: CustomVec<_> = vec![1, 2].into();
+    vec.extend(&[3, 4]);
+    for i in &mut vec {
+        *i *= 10;
+    }
+    println!("{:?}", Vec::from(vec));
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{CustomVec, OutOfBounds, RawDynamicArray, TryReserveError};
+    use std::cell::Cell;
+    use std::panic::{catch_unwind, AssertUnwindSafe};
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    /// Counts its drops in a counter it borrows.
+    struct Inspector<'a>(&'a Cell<usize>);
+
+    impl<'a> Drop for Inspector<'a> {
+        fn drop(&mut self) {
+            self.0.set(self.0.get() + 1);
+        }
+    }
+
+    #[test]
+    fn test_elements_are_dropped_once() {
+        // Declaring the counter after the vector wouldn't compile, since `CustomVec` implements
+        // `Drop` and drop check assumes that uses the borrow. That can't be tested here though:
+        // a `compile_fail` doctest needs a library target, and this is a binary.
+        let drops = Cell::new(0);
+        let mut vec = CustomVec::new();
+        for _ in 0..5 {
+            vec.push(Inspector(&drops));
+        }
+        drop(vec.pop());
+        drop(vec.remove(1));
+        assert_eq!(drops.get(), 2);
+        drop(vec);
+        assert_eq!(drops.get(), 5);
+    }
+
+    #[test]
+    fn test_into_iter_drops_the_rest() {
+        let drops = Cell::new(0);
+        let mut vec = CustomVec::new();
+        for _ in 0..4 {
+            vec.push(Inspector(&drops));
+        }
+        let mut iter = vec.into_iter();
+        drop(iter.next());
+        drop(iter.next_back());
+        assert_eq!(drops.get(), 2);
+        drop(iter);
+        assert_eq!(drops.get(), 4);
+    }
+
+    #[test]
+    fn test_zero_sized_types() {
+        let mut vec = CustomVec::new();
+        for _ in 0..10 {
+            vec.push(());
+        }
+        assert_eq!(vec.raw.cap, usize::MAX);
+        assert_eq!(vec.len(), 10);
+        assert_eq!(vec.pop(), Some(()));
+        vec.insert(3, ());
+        assert_eq!(vec.try_remove(0), Ok(()));
+        assert_eq!(vec.len(), 9);
+
+        let mut drain = vec.drain(..);
+        assert_eq!(drain.size_hint(), (9, Some(9)));
+        assert_eq!(drain.next_back(), Some(()));
+        assert_eq!(drain.count(), 8);
+        assert!(vec.is_empty());
+
+        for _ in 0..6 {
+            vec.push(());
+        }
+        let mut iter = vec.into_iter();
+        assert_eq!(iter.next(), Some(()));
+        assert_eq!(iter.next_back(), Some(()));
+        assert_eq!(iter.size_hint(), (4, Some(4)));
+        assert_eq!(iter.count(), 4);
+    }
+
+    #[test]
+    fn test_zero_sized_elements_are_dropped_once() {
+        static DROPS: AtomicUsize = AtomicUsize::new(0);
+        struct Zst;
+        impl Drop for Zst {
+            fn drop(&mut self) {
+                DROPS.fetch_add(1, Ordering::SeqCst);
+            }
+        }
+
+        let mut vec = CustomVec::new();
+        for _ in 0..7 {
+            vec.push(Zst);
+        }
+        drop(vec.pop());
+        drop(vec.drain(..).take(2).collect::<Vec<_>>());
+        assert_eq!(DROPS.load(Ordering::SeqCst), 7);
+        for _ in 0..3 {
+            vec.push(Zst);
+        }
+        drop(vec.into_iter().next());
+        assert_eq!(DROPS.load(Ordering::SeqCst), 10);
+    }
+
+    #[test]
+    fn test_capacity() {
+        let mut vec = CustomVec::with_capacity(3);
+        assert_eq!(vec.capacity(), 3);
+        for i in 0..4 {
+            vec.push(i);
+        }
+        assert_eq!(vec.capacity(), 6);
+
+        vec.reserve(1);
+        assert_eq!(vec.capacity(), 6);
+        vec.reserve(5);
+        assert_eq!(vec.capacity(), 12);
+        vec.reserve_exact(10);
+        assert_eq!(vec.capacity(), 14);
+
+        vec.shrink_to_fit();
+        assert_eq!(vec.capacity(), 4);
+        assert_eq!(&vec[..], [0, 1, 2, 3]);
+        while vec.pop().is_some() {}
+        vec.shrink_to_fit();
+        assert_eq!(vec.capacity(), 0);
+        vec.push(7);
+        assert_eq!(&vec[..], [7]);
+
+        // Nothing is allocated, so nothing may be freed either.
+        drop(CustomVec::<String>::with_capacity(0));
+        drop(CustomVec::<String>::new());
+    }
+
+    #[test]
+    fn test_fallible_reservation() {
+        let mut vec = CustomVec::new();
+        vec.push(1u64);
+        assert_eq!(vec.try_reserve(10), Ok(()));
+        assert!(vec.capacity() >= 11);
+
+        assert_eq!(
+            vec.try_reserve(usize::MAX),
+            Err(TryReserveError::CapacityOverflow)
+        );
+        // Fits in a `usize` but not in an `isize` once counted in bytes.
+        assert_eq!(
+            vec.try_reserve(usize::MAX / 8),
+            Err(TryReserveError::CapacityOverflow)
+        );
+        // Fits, but no allocator hands out this much.
+        assert!(matches!(
+            vec.try_reserve(isize::MAX as usize / 8 - 1),
+            Err(TryReserveError::AllocError { .. })
+        ));
+        assert_eq!(&vec[..], [1]);
+
+        vec.shrink_to_fit();
+        assert_eq!(vec.try_push(2), Ok(()));
+        let mut units = CustomVec::new();
+        assert_eq!(units.try_reserve(usize::MAX), Ok(()));
+        units.len = usize::MAX;
+        assert_eq!(
+            units.try_push(()),
+            Err(((), TryReserveError::CapacityOverflow))
+        );
+        units.len = 0;
+
+        // Doubling would overflow, but the request itself fits.
+        let cap = isize::MAX as usize / 2 + 1;
+        assert_eq!(RawDynamicArray::<u8>::grown_capacity(4, 5), 8);
+        assert_eq!(RawDynamicArray::<u8>::grown_capacity(cap, cap + 1), cap + 1);
+    }
+
+    fn numbers(range: std::ops::Range<i32>) -> CustomVec<i32> {
+        let mut vec = CustomVec::new();
+        for i in range {
+            vec.push(i);
+        }
+        vec
+    }
+
+    #[test]
+    fn test_drain_range() {
+        let mut vec = numbers(0..10);
+        assert_eq!(vec.drain(2..5).collect::<Vec<_>>(), [2, 3, 4]);
+        assert_eq!(&vec[..], [0, 1, 5, 6, 7, 8, 9]);
+        let mut drain = vec.drain(4..=5);
+        assert_eq!(drain.next_back(), Some(8));
+        drop(drain);
+        assert_eq!(&vec[..], [0, 1, 5, 6, 9]);
+        assert_eq!(vec.drain(5..).count(), 0);
+        assert_eq!(vec.drain(..).count(), 5);
+        assert!(vec.is_empty());
+
+        let drops = Cell::new(0);
+        let mut vec = CustomVec::new();
+        for _ in 0..6 {
+            vec.push(Inspector(&drops));
+        }
+        let mut drain = vec.drain(1..4);
+        drop(drain.next());
+        assert_eq!(drops.get(), 1);
+        drop(drain);
+        assert_eq!((drops.get(), vec.len()), (3, 3));
+
+        // A leaked drain leaks the range and the tail, but drops nothing twice.
+        std::mem::forget(vec.drain(1..2));
+        assert_eq!(vec.len(), 1);
+        drop(vec);
+        assert_eq!(drops.get(), 4);
+    }
+
+    #[test]
+    #[should_panic(expected = "range end index 4 out of range for slice of length 3")]
+    fn test_drain_out_of_bounds() {
+        numbers(0..3).drain(1..4);
+    }
+
+    #[test]
+    fn test_splice() {
+        let mut vec = numbers(1..6);
+        let removed = vec.splice(1..3, [10, 20, 30]).collect::<Vec<_>>();
+        assert_eq!(removed, [2, 3]);
+        assert_eq!(&vec[..], [1, 10, 20, 30, 4, 5]);
+
+        vec.splice(..2, None);
+        assert_eq!(&vec[..], [20, 30, 4, 5]);
+        vec.splice(4.., 6..9);
+        assert_eq!(&vec[..], [20, 30, 4, 5, 6, 7, 8]);
+        // Nothing is replaced before the splice is dropped.
+        let mut splice = vec.splice(1..6, [0]);
+        assert_eq!(splice.next(), Some(30));
+        drop(splice);
+        assert_eq!(&vec[..], [20, 0, 8]);
+    }
+
+    #[test]
+    fn test_splice_moves_the_buffer() {
+        let mut vec = CustomVec::new();
+        for i in 0..4 {
+            vec.push(i.to_string());
+        }
+        vec.shrink_to_fit();
+        let cap = vec.capacity();
+        let removed = vec
+            .splice(1..3, (10..20).map(|i| i.to_string()))
+            .collect::<Vec<_>>();
+        assert_eq!(removed, ["1", "2"]);
+        assert!(vec.capacity() > cap);
+        assert_eq!(
+            &vec[..],
+            ["0", "10", "11", "12", "13", "14", "15", "16", "17", "18", "19", "3"]
+        );
+    }
+
+    #[test]
+    fn test_retain_and_dedup() {
+        let mut vec = numbers(0..10);
+        vec.retain(|&i| i % 3 != 0);
+        assert_eq!(&vec[..], [1, 2, 4, 5, 7, 8]);
+        vec.retain_mut(|i| {
+            *i += 1;
+            *i < 8
+        });
+        assert_eq!(&vec[..], [2, 3, 5, 6]);
+
+        let mut vec = numbers(0..10);
+        vec.dedup_by_key(|i| *i / 4);
+        assert_eq!(&vec[..], [0, 4, 8]);
+        let mut vec = numbers(0..1);
+        vec.dedup_by_key(|_| 0);
+        assert_eq!(&vec[..], [0]);
+    }
+
+    #[test]
+    fn test_retain_keeps_the_rest_on_panic() {
+        let drops = Cell::new(0);
+        let mut vec = CustomVec::new();
+        for _ in 0..6 {
+            vec.push(Inspector(&drops));
+        }
+        let mut seen = 0;
+        let res = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+            vec.retain(|_| {
+                seen += 1;
+                assert!(seen < 4);
+                seen % 2 == 0
+            })
+        }));
+        assert!(res.is_err());
+        // Two of the first three were dropped, the one that panicked and the two after it kept.
+        assert_eq!((drops.get(), vec.len()), (2, 4));
+        drop(vec);
+        assert_eq!(drops.get(), 6);
+    }
+
+    #[test]
+    fn test_split_off_append_truncate_swap_remove() {
+        let mut vec = numbers(0..6);
+        let mut tail = vec.split_off(4);
+        assert_eq!((&vec[..], &tail[..]), (&[0, 1, 2, 3][..], &[4, 5][..]));
+        assert!(vec.split_off(4).is_empty());
+
+        tail.append(&mut vec);
+        assert!(vec.is_empty());
+        assert_eq!(&tail[..], [4, 5, 0, 1, 2, 3]);
+
+        assert_eq!(tail.swap_remove(1), 5);
+        assert_eq!(&tail[..], [4, 3, 0, 1, 2]);
+        assert_eq!(tail.swap_remove(4), 2);
+        tail.truncate(10);
+        assert_eq!(tail.len(), 4);
+        tail.truncate(1);
+        assert_eq!(&tail[..], [4]);
+
+        let drops = Cell::new(0);
+        let mut vec = CustomVec::new();
+        for _ in 0..5 {
+            vec.push(Inspector(&drops));
+        }
+        vec.truncate(2);
+        assert_eq!(drops.get(), 3);
+        drop(vec.swap_remove(0));
+        assert_eq!((drops.get(), vec.len()), (4, 1));
+    }
+
+    #[test]
+    #[should_panic(expected = "swap_remove index (is 3) should be < len (is 3)")]
+    fn test_swap_remove_out_of_bounds() {
+        numbers(0..3).swap_remove(3);
+    }
+
+    #[test]
+    fn test_insert_and_remove_bounds() {
+        let mut vec = numbers(0..3);
+        vec.insert(3, 3);
+        vec.insert(0, -1);
+        assert_eq!(&vec[..], [-1, 0, 1, 2, 3]);
+        assert_eq!(vec.remove(4), 3);
+        assert_eq!(
+            vec.try_insert(5, 9),
+            Err((9, OutOfBounds { index: 5, len: 4 }))
+        );
+        assert_eq!(vec.try_remove(4), Err(OutOfBounds { index: 4, len: 4 }));
+        assert_eq!(vec.try_remove(0), Ok(-1));
+        assert_eq!(vec.try_insert(1, 7), Ok(()));
+        assert_eq!(&vec[..], [0, 7, 1, 2]);
+        assert_eq!(
+            OutOfBounds { index: 4, len: 4 }.to_string(),
+            "index 4 out of bounds for length 4"
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "insertion index (is 4) should be <= len (is 3)")]
+    fn test_insert_out_of_bounds() {
+        numbers(0..3).insert(4, 0);
+    }
+
+    #[test]
+    #[should_panic(expected = "removal index (is 0) should be < len (is 0)")]
+    fn test_remove_out_of_bounds() {
+        CustomVec::<i32>::new().remove(0);
+    }
+
+    /// Counts its drops and clones, and panics when dropped or cloned if told to.
+    struct Bomb<'a> {
+        drops: &'a Cell<usize>,
+        clones: &'a Cell<usize>,
+        panic_on_drop: bool,
+        panic_on_clone: bool,
+    }
+
+    impl<'a> Bomb<'a> {
+        fn vec(counts: &'a (Cell<usize>, Cell<usize>), len: usize) -> CustomVec<Bomb<'a>> {
+            let mut vec = CustomVec::new();
+            for _ in 0..len {
+                vec.push(Bomb {
+                    drops: &counts.0,
+                    clones: &counts.1,
+                    panic_on_drop: false,
+                    panic_on_clone: false,
+                });
+            }
+            vec
+        }
+    }
+
+    impl<'a> Clone for Bomb<'a> {
+        fn clone(&self) -> Self {
+            assert!(!self.panic_on_clone, "clone panicked");
+            self.clones.set(self.clones.get() + 1);
+            Bomb {
+                panic_on_drop: false,
+                ..*self
+            }
+        }
+    }
+
+    impl<'a> Drop for Bomb<'a> {
+        fn drop(&mut self) {
+            self.drops.set(self.drops.get() + 1);
+            assert!(!self.panic_on_drop, "drop panicked");
+        }
+    }
+
+    #[test]
+    fn test_panicking_drops_drop_everything_else_once() {
+        let counts = Default::default();
+        let mut vec = Bomb::vec(&counts, 6);
+        vec[3].panic_on_drop = true;
+        assert!(catch_unwind(AssertUnwindSafe(|| vec.truncate(1))).is_err());
+        assert_eq!((counts.0.get(), vec.len()), (5, 1));
+        drop(vec);
+        assert_eq!(counts.0.get(), 6);
+
+        let counts = Default::default();
+        let mut vec = Bomb::vec(&counts, 6);
+        vec[0].panic_on_drop = true;
+        assert!(catch_unwind(AssertUnwindSafe(|| drop(vec))).is_err());
+        assert_eq!(counts.0.get(), 6);
+
+        let counts = Default::default();
+        let mut vec = Bomb::vec(&counts, 6);
+        vec[2].panic_on_drop = true;
+        assert!(catch_unwind(AssertUnwindSafe(|| drop(vec.drain(1..4)))).is_err());
+        assert_eq!((counts.0.get(), vec.len()), (3, 3));
+        drop(vec);
+        assert_eq!(counts.0.get(), 6);
+
+        let counts = Default::default();
+        let mut vec = Bomb::vec(&counts, 6);
+        vec[2].panic_on_drop = true;
+        let mut iter = vec.into_iter();
+        drop(iter.next());
+        assert!(catch_unwind(AssertUnwindSafe(|| drop(iter))).is_err());
+        assert_eq!(counts.0.get(), 6);
+
+        let counts = Default::default();
+        let mut vec = Bomb::vec(&counts, 6);
+        vec[1].panic_on_drop = true;
+        vec[4].panic_on_drop = true;
+        let dropped = catch_unwind(AssertUnwindSafe(|| vec.dedup_by_key(|_| 0)));
+        assert!(dropped.is_err());
+        assert_eq!((counts.0.get(), vec.len()), (1, 5));
+        let dropped = catch_unwind(AssertUnwindSafe(|| vec.retain(|bomb| !bomb.panic_on_drop)));
+        assert!(dropped.is_err());
+        assert_eq!((counts.0.get(), vec.len()), (2, 4));
+        drop(vec);
+        assert_eq!(counts.0.get(), 6);
+    }
+
+    #[test]
+    fn test_panicking_clones_keep_the_copies_made() {
+        let counts = Default::default();
+        let mut source = Bomb::vec(&counts, 4);
+        source[2].panic_on_clone = true;
+        let mut vec = Bomb::vec(&counts, 1);
+        let cloned = catch_unwind(AssertUnwindSafe(|| vec.extend_from_slice(&source)));
+        assert!(cloned.is_err());
+        assert_eq!((counts.1.get(), vec.len()), (2, 3));
+
+        source[2].panic_on_clone = false;
+        vec.extend_from_slice(&source[2..]);
+        assert_eq!((counts.1.get(), vec.len()), (4, 5));
+        drop(vec);
+        drop(source);
+        assert_eq!(counts.0.get(), 9);
+    }
+
+    #[test]
+    fn test_macro_and_conversions() {
+        let empty: CustomVec<i32> = custom_vec![];
+        assert_eq!(empty, CustomVec::default());
+        assert_eq!(custom_vec![1, 2, 3,], [1, 2, 3]);
+        assert_eq!(custom_vec!["a".to_string(); 3], vec!["a"; 3]);
+        assert!(custom_vec![(); 0].is_empty());
+
+        let std_vec = Vec::with_capacity(10);
+        let data = std_vec.as_ptr();
+        let mut vec = CustomVec::from(std_vec);
+        vec.extend([1, 2, 3]);
+        assert_eq!((vec.capacity(), vec.len()), (10, 3));
+        let std_vec = Vec::from(vec);
+        assert_eq!(std_vec.as_ptr(), data);
+        assert_eq!((std_vec.capacity(), &std_vec[..]), (10, &[1, 2, 3][..]));
+        assert_eq!(Vec::from(CustomVec::<String>::new()), Vec::<String>::new());
+        assert_eq!(CustomVec::from(vec![(); 4]).len(), 4);
+
+        let strings = CustomVec::from(["x".to_string(), "y".to_string()]);
+        assert_eq!(strings, ["x", "y"]);
+        assert_eq!(strings, vec!["x", "y"]);
+        assert_eq!(strings, &["x", "y"][..]);
+    }
+
+    #[test]
+    fn test_iterators() {
+        let mut vec = (1..=4).collect::<CustomVec<_>>();
+        vec.extend(5..7);
+        vec.extend(&[7, 8]);
+        for i in &mut vec {
+            *i *= 2;
+        }
+        assert_eq!((&vec).into_iter().sum::<i32>(), 72);
+        assert_eq!(vec.iter().len(), 8);
+
+        // `next_back` used to call itself instead of the raw iterator.
+        let strings = vec.iter().map(i32::to_string).collect::<CustomVec<_>>();
+        let mut iter = strings.into_iter().rev();
+        assert_eq!(iter.next().as_deref(), Some("16"));
+        assert_eq!(
+            iter.collect::<Vec<_>>(),
+            ["14", "12", "10", "8", "6", "4", "2"]
+        );
+        let mut total = 0;
+        for i in vec {
+            total += i;
+        }
+        assert_eq!(total, 72);
+    }
+
+    #[test]
+    fn test_comparison_traits() {
+        use std::collections::hash_map::DefaultHasher;
+        use std::collections::HashSet;
+        use std::hash::{Hash, Hasher};
+
+        let vec = custom_vec![3, 1, 2];
+        let copy = vec.clone();
+        assert_eq!(vec, copy);
+        assert_ne!(vec, custom_vec![3, 1]);
+        assert!(custom_vec![3, 1] < vec && vec < custom_vec![3, 2]);
+        assert_eq!(vec.cmp(&copy), std::cmp::Ordering::Equal);
+        assert_eq!(
+            custom_vec![1.0, f64::NAN].partial_cmp(&custom_vec![1.0, 2.0]),
+            None
+        );
+
+        let hash = |value: &dyn Fn(&mut DefaultHasher)| {
+            let mut hasher = DefaultHasher::new();
+            value(&mut hasher);
+            hasher.finish()
+        };
+        assert_eq!(hash(&|h| vec.hash(h)), hash(&|h| [3, 1, 2][..].hash(h)));
+        let set = IntoIterator::into_iter([vec, custom_vec![1]]).collect::<HashSet<_>>();
+        assert!(set.contains(&custom_vec![3, 1, 2]));
+        assert_eq!(format!("{:?}", custom_vec!["a", "b"]), r#"["a", "b"]"#);
+    }
+
+    #[test]
+    fn test_index_ranges() {
+        let mut vec = custom_vec![0, 1, 2, 3, 4];
+        assert_eq!(vec[1..3], [1, 2]);
+        assert_eq!(vec[..=1], [0, 1]);
+        assert_eq!(vec[3..], [3, 4]);
+        vec[2..].fill(9);
+        vec[0] = 5;
+        assert_eq!(vec, [5, 1, 9, 9, 9]);
+        assert_eq!(vec.get(5), None);
+    }
+
+    #[test]
+    #[should_panic(expected = "range end index 6 out of range for slice of length 5")]
+    fn test_index_out_of_bounds() {
+        let vec = custom_vec![0; 5];
+        let _ = &vec[2..6];
+    }
+
+    #[test]
+    fn test_variance_and_auto_traits() {
+        fn shorten<'a>(vec: CustomVec<&'static str>) -> CustomVec<&'a str> {
+            vec
+        }
+        fn assert_send_sync<T: Send + Sync>() {}
+
+        let mut vec = CustomVec::new();
+        vec.push("static");
+        let local = String::from("local");
+        let mut vec = shorten(vec);
+        vec.push(&local);
+        assert_eq!(&vec[..], ["static", "local"]);
+        assert_send_sync::<CustomVec<String>>();
+    }
+}